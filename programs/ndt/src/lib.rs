@@ -4,6 +4,12 @@ use std::str::FromStr;
 
 declare_id!("NDT111111111111111111111111111111111111111111");
 
+// Программа staking, принимающая миграцию позиций
+pub mod staking_program {
+    use super::*;
+    declare_id!("STAKING111111111111111111111111111111111111111");
+}
+
 #[program]
 pub mod ndt {
     use super::*;
@@ -126,7 +132,7 @@ pub mod ndt {
                 ctx.accounts.token_program.to_account_info(),
                 anchor_spl::token::Transfer {
                     from: staker.to_account_info(),
-                    to: staking_account.to_account_info(),
+                    to: ctx.accounts.stake_custody.to_account_info(),
                     authority: authority.to_account_info(),
                 },
             ),
@@ -174,15 +180,19 @@ pub mod ndt {
             ErrorCode::LockPeriodNotExpired
         );
         
-        // Возвращаем токены
+        // Возвращаем токены из хранилища стейкинга
+        let seeds = &[b"ndt".as_ref(), &[ctx.bumps.ndt]];
+        let signer = &[&seeds[..]];
+        
         anchor_spl::token::transfer(
-            CpiContext::new(
+            CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 anchor_spl::token::Transfer {
-                    from: staking_account.to_account_info(),
+                    from: ctx.accounts.stake_custody.to_account_info(),
                     to: staker.to_account_info(),
-                    authority: authority.to_account_info(),
+                    authority: ndt.to_account_info(),
                 },
+                signer,
             ),
             amount,
         )?;
@@ -251,6 +261,51 @@ pub mod ndt {
         
         Ok(())
     }
+
+    // Создание хранилища застейканных токенов
+    pub fn initialize_stake_custody(ctx: Context<InitializeStakeCustody>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.ndt.authority, ErrorCode::Unauthorized);
+        
+        Ok(())
+    }
+
+    // Закрытие позиции при переносе в программу staking (только через CPI из staking::migrate_from_ndt).
+    // Застейканные токены переходят в хранилище staking.
+    pub fn release_for_migration(ctx: Context<ReleaseForMigration>) -> Result<()> {
+        let staker = &ctx.accounts.staker;
+        let staking_account = &mut ctx.accounts.staking_account;
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        let amount = staking_account.amount;
+        
+        let seeds = &[b"ndt".as_ref(), &[ctx.bumps.ndt]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.stake_custody.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.ndt.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        // Обнуляем позицию: rewards до текущего момента рассчитывает staking
+        staking_account.amount = 0;
+        staking_account.last_claim_time = current_time;
+        
+        emit!(MigratedToStakingEvent {
+            staker: staker.key(),
+            amount,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
 }
 
 // Helper функции
@@ -335,6 +390,13 @@ pub struct ClaimRewardsEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct MigratedToStakingEvent {
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
 // Contexts
 #[derive(Accounts)]
 pub struct Initialize<'info> {
@@ -387,6 +449,8 @@ pub struct Stake<'info> {
         bump
     )]
     pub staking_account: Account<'info, StakingAccount>,
+    #[account(mut, seeds = [b"stake_custody"], bump)]
+    pub stake_custody: Account<'info, TokenAccount>,
     #[account(mut)]
     pub rewards_account: Account<'info, TokenAccount>,
     pub authority: Signer<'info>,
@@ -402,6 +466,8 @@ pub struct Unstake<'info> {
     pub staker: Account<'info, TokenAccount>,
     #[account(mut, seeds = [b"staking", staker.key().as_ref()], bump)]
     pub staking_account: Account<'info, StakingAccount>,
+    #[account(mut, seeds = [b"stake_custody"], bump)]
+    pub stake_custody: Account<'info, TokenAccount>,
     #[account(mut)]
     pub rewards_account: Account<'info, TokenAccount>,
     pub authority: Signer<'info>,
@@ -422,6 +488,44 @@ pub struct ClaimRewards<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeStakeCustody<'info> {
+    #[account(seeds = [b"ndt"], bump)]
+    pub ndt: Account<'info, NdtState>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"stake_custody"],
+        bump,
+        token::mint = mint,
+        token::authority = ndt
+    )]
+    pub stake_custody: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseForMigration<'info> {
+    pub staker: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"staking", staker.key().as_ref()], bump)]
+    pub staking_account: Account<'info, StakingAccount>,
+    #[account(seeds = [b"ndt"], bump)]
+    pub ndt: Account<'info, NdtState>,
+    #[account(mut, seeds = [b"stake_custody"], bump)]
+    pub stake_custody: Account<'info, TokenAccount>,
+    // Хранилище стейкинга; адрес проверяет программа staking, подписывающая migrator
+    #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+    #[account(seeds = [b"ndt_migrator"], bump, seeds::program = staking_program::ID)]
+    pub migrator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
 // Error codes
 #[error_code]
 pub enum ErrorCode {
@@ -1,9 +1,19 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 use std::str::FromStr;
 
 declare_id!("STAKING111111111111111111111111111111111111111");
 
+// Программа ndt со встроенным стейкингом (источник миграции)
+pub mod ndt_program {
+    use super::*;
+    declare_id!("NDT111111111111111111111111111111111111111111");
+}
+
 #[program]
 pub mod staking {
     use super::*;
@@ -27,6 +37,43 @@ pub mod staking {
         staking_pool.silver_base_apy = 10; // 10%
         staking_pool.gold_base_apy = 15; // 15%
         
+        // Допустимые сроки блокировки и их множители
+        staking_pool.lock_options = vec![
+            LockOption { duration: 0, multiplier: 100 }, // 1x multiplier
+            LockOption { duration: 90 * 24 * 60 * 60, multiplier: 120 }, // 3 месяца, 1.2x
+            LockOption { duration: 180 * 24 * 60 * 60, multiplier: 150 }, // 6 месяцев, 1.5x
+            LockOption { duration: 365 * 24 * 60 * 60, multiplier: 200 }, // 12 месяцев, 2x
+        ];
+        
+        Ok(())
+    }
+
+    // Обновление допустимых сроков блокировки
+    pub fn set_lock_options(ctx: Context<SetLockOptions>, lock_options: Vec<LockOption>) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        require!(
+            !lock_options.is_empty() && lock_options.len() <= MAX_LOCK_OPTIONS,
+            ErrorCode::InvalidLockOptions
+        );
+        
+        for (i, option) in lock_options.iter().enumerate() {
+            require!(option.duration >= 0 && option.multiplier > 0, ErrorCode::InvalidLockOptions);
+            require!(
+                lock_options[..i].iter().all(|other| other.duration != option.duration),
+                ErrorCode::InvalidLockOptions
+            );
+        }
+        
+        staking_pool.lock_options = lock_options;
+        
+        emit!(LockOptionsUpdatedEvent {
+            lock_options: staking_pool.lock_options.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
         Ok(())
     }
 
@@ -34,39 +81,93 @@ pub mod staking {
     pub fn stake(
         ctx: Context<Stake>,
         amount: u64,
-        lock_period_months: u8,
+        lock_duration: i64,
+        referrer: Option<Pubkey>,
     ) -> Result<()> {
         let staking_pool = &mut ctx.accounts.staking_pool;
         let staker = &mut ctx.accounts.staker;
+        let authority = &ctx.accounts.authority;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        
+        // Истекшая эпоха закрывается до изменения стейка, чтобы ее итоги не включали эту операцию
+        roll_emission_epoch(staking_pool, &ctx.accounts.leaderboard, Clock::get()?.unix_timestamp);
+        
+        // Реферер пользователя фиксируется при первом стейке и переходит на все его позиции
+        let referral = &mut ctx.accounts.referral;
+        if referral.owner == Pubkey::default() {
+            if let Some(referrer) = referrer {
+                require!(referrer != authority.key(), ErrorCode::SelfReferral);
+                
+                let referrer_account = ctx.accounts.referrer_account.as_mut().ok_or(ErrorCode::ReferrerNotRegistered)?;
+                require!(referrer_account.referrer == referrer, ErrorCode::ReferrerNotRegistered);
+                
+                referrer_account.referee_count = referrer_account.referee_count.checked_add(1).unwrap();
+            }
+            
+            referral.owner = authority.key();
+            referral.referrer = referrer;
+        } else {
+            require!(
+                referrer.is_none() || referrer == referral.referrer,
+                ErrorCode::ReferrerAlreadySet
+            );
+        }
+        staker.referrer = referral.referrer;
+        
+        // Переводим токены в хранилище стейкинга
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.staker_token_account.to_account_info(),
+                    to: ctx.accounts.stake_vault.to_account_info(),
+                    authority: authority.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        // Выпускаем NFT позиции: владелец NFT распоряжается стейком
+        let seeds = &[b"staking".as_ref(), &[ctx.bumps.staking_pool]];
+        let signer = &[&seeds[..]];
+        
+        mint_position_nft(
+            &ctx.accounts.token_program,
+            &ctx.accounts.position_mint,
+            &ctx.accounts.position_token_account,
+            staking_pool,
+            signer,
+        )?;
+        
+        staker.staker = authority.key();
+        staker.position_mint = ctx.accounts.position_mint.key();
         staker.amount = staker.amount.checked_add(amount).unwrap();
+        sync_revenue_debt(staking_pool, staker);
+        update_staker_count(staking_pool, 0, staker.amount);
+        update_leaderboard(&mut ctx.accounts.leaderboard, staker.key(), staker.amount);
         
         // Обновляем общий стейкинг
         staking_pool.total_staked = staking_pool.total_staked.checked_add(amount).unwrap();
         
         // Рассчитываем APY на основе уровня и срока
-        let tier_multiplier = get_tier_multiplier(staker.total_staked);
-        let time_multiplier = get_time_multiplier(lock_period_months);
-        let base_apy = get_base_apy(staker.total_staked, staking_pool);
-        
-        let final_apy = base_apy
-            .checked_mul(tier_multiplier)
-            .unwrap()
-            .checked_div(100)
-            .unwrap()
-            .checked_mul(time_multiplier)
-            .unwrap()
-            .checked_div(100)
-            .unwrap();
+        let time_multiplier = get_time_multiplier(staking_pool, lock_duration)?;
+        let final_apy = calculate_apy(staking_pool, staker.total_staked, time_multiplier);
         
         staker.apy = final_apy;
-        staker.lock_period_months = lock_period_months;
+        staker.time_multiplier = time_multiplier;
+        staker.lock_duration = lock_duration;
         staker.stake_time = Clock::get()?.unix_timestamp;
+        staker.unlock_at = staker.stake_time.checked_add(lock_duration).unwrap();
         staker.last_claim_time = Clock::get()?.unix_timestamp;
+        checkpoint_voting_power(staking_pool, staker, Clock::get()?.unix_timestamp);
         
         emit!(StakeEvent {
             staker: staker.key(),
+            position_mint: staker.position_mint,
             amount,
-            lock_period_months,
+            lock_duration,
+            unlock_at: staker.unlock_at,
             apy: final_apy,
             timestamp: Clock::get()?.unix_timestamp,
         });
@@ -74,396 +175,2820 @@ pub mod staking {
         Ok(())
     }
 
-    // Unstaking токенов
-    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+    // Перенос позиции из встроенного стейкинга программы ndt (однократно)
+    pub fn migrate_from_ndt(ctx: Context<MigrateFromNdt>) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
+        let authority = &ctx.accounts.authority;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        
+        // Владелец и PDA проверены ограничениями, здесь проверяется тип аккаунта
+        let ndt_stake = read_ndt_staking_account(&ctx.accounts.ndt_staking_account)?;
+        require!(ndt_stake.amount > 0, ErrorCode::NothingToMigrate);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        // Истекшая эпоха закрывается до изменения стейка, чтобы ее итоги не включали эту операцию
+        roll_emission_epoch(staking_pool, &ctx.accounts.leaderboard, current_time);
+        
+        // Закрываем позицию в ndt, чтобы ее нельзя было использовать повторно;
+        // ndt переводит застейканные токены из своего хранилища в хранилище стейкинга
+        let vault_balance = ctx.accounts.stake_vault.amount;
+        let migrator_seeds = &[b"ndt_migrator".as_ref(), &[ctx.bumps.ndt_migrator]];
+        let release_ix = Instruction {
+            program_id: ndt_program::ID,
+            accounts: vec![
+                AccountMeta::new_readonly(ctx.accounts.ndt_staker_token_account.key(), false),
+                AccountMeta::new(ctx.accounts.ndt_staking_account.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.ndt_state.key(), false),
+                AccountMeta::new(ctx.accounts.ndt_stake_custody.key(), false),
+                AccountMeta::new(ctx.accounts.stake_vault.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.ndt_migrator.key(), true),
+                AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+            ],
+            data: hash(b"global:release_for_migration").to_bytes()[..8].to_vec(),
+        };
+        invoke_signed(
+            &release_ix,
+            &[
+                ctx.accounts.ndt_staker_token_account.to_account_info(),
+                ctx.accounts.ndt_staking_account.to_account_info(),
+                ctx.accounts.ndt_state.to_account_info(),
+                ctx.accounts.ndt_stake_custody.to_account_info(),
+                ctx.accounts.stake_vault.to_account_info(),
+                ctx.accounts.ndt_migrator.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.ndt_program.to_account_info(),
+            ],
+            &[&migrator_seeds[..]],
+        )?;
+        
+        ctx.accounts.stake_vault.reload()?;
+        require!(
+            ctx.accounts.stake_vault.amount == vault_balance.checked_add(ndt_stake.amount).unwrap(),
+            ErrorCode::InvalidNdtStakingAccount
+        );
+        
+        let seeds = &[b"staking".as_ref(), &[ctx.bumps.staking_pool]];
+        let signer = &[&seeds[..]];
+        
+        mint_position_nft(
+            &ctx.accounts.token_program,
+            &ctx.accounts.position_mint,
+            &ctx.accounts.position_token_account,
+            staking_pool,
+            signer,
+        )?;
+        
+        // Сохраняем оставшийся срок блокировки и лучший доступный множитель
+        let lock_period = i64::try_from(ndt_stake.lock_period).map_err(|_| error!(ErrorCode::InvalidLockDuration))?;
+        let lock_duration = ndt_stake.stake_time
+            .checked_add(lock_period)
+            .unwrap()
+            .checked_sub(current_time)
+            .unwrap()
+            .max(0);
+        let time_multiplier = floor_time_multiplier(staking_pool, lock_duration);
+        
+        // Неполученные rewards ndt переходят в новую позицию
+        let settled_rewards = calculate_rewards(
+            ndt_stake.amount,
+            ndt_stake.apr,
+            current_time.checked_sub(ndt_stake.last_claim_time).unwrap(),
+        )?;
+        
+        staker.staker = authority.key();
+        staker.position_mint = ctx.accounts.position_mint.key();
+        staker.amount = ndt_stake.amount;
+        staker.apy = calculate_apy(staking_pool, staker.total_staked, time_multiplier);
+        staker.time_multiplier = time_multiplier;
+        staker.lock_duration = lock_duration;
+        staker.stake_time = ndt_stake.stake_time;
+        staker.unlock_at = current_time.checked_add(lock_duration).unwrap();
+        staker.last_claim_time = current_time;
+        staker.accrued_rewards = settled_rewards;
+        sync_revenue_debt(staking_pool, staker);
+        update_staker_count(staking_pool, 0, staker.amount);
+        update_leaderboard(&mut ctx.accounts.leaderboard, staker.key(), staker.amount);
+        
+        staking_pool.total_staked = staking_pool.total_staked.checked_add(ndt_stake.amount).unwrap();
+        checkpoint_voting_power(staking_pool, staker, current_time);
+        
+        // Rewards ndt выплачиваются сразу в пределах лимита эпохи
+        let payout = emission_payout(staking_pool, staker, ctx.accounts.rewards_vault.amount);
+        if payout > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: ctx.accounts.rewards_vault.to_account_info(),
+                        to: ctx.accounts.rewards_account.to_account_info(),
+                        authority: staking_pool.to_account_info(),
+                    },
+                    signer,
+                ),
+                payout,
+            )?;
+            
+            record_emission(staking_pool, staker, &mut None, payout, ctx.accounts.rewards_vault.amount, current_time)?;
+        }
+        
+        let migration_record = &mut ctx.accounts.migration_record;
+        migration_record.ndt_staking_account = ctx.accounts.ndt_staking_account.key();
+        migration_record.staker = staker.key();
+        migration_record.amount = ndt_stake.amount;
+        migration_record.migrated_at = current_time;
+        
+        emit!(MigratedFromNdtEvent {
+            staker: staker.key(),
+            position_mint: staker.position_mint,
+            ndt_staking_account: migration_record.ndt_staking_account,
+            amount: ndt_stake.amount,
+            unlock_at: staker.unlock_at,
+            settled_rewards,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Запрос на вывод: создает тикет с периодом охлаждения
+    pub fn request_unstake(ctx: Context<RequestUnstake>, amount: u64) -> Result<()> {
         let staking_pool = &mut ctx.accounts.staking_pool;
         let staker = &mut ctx.accounts.staker;
+        let ticket = &mut ctx.accounts.ticket;
+        let authority = &ctx.accounts.authority;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
         
         require!(staker.amount >= amount, ErrorCode::InsufficientStake);
         
+        // Репортер обязан сохранять минимальный залог
+        require!(
+            !staker.is_reporter || staker.amount - amount >= staking_pool.min_reporter_stake,
+            ErrorCode::ReporterStakeTooLow
+        );
+        
         let current_time = Clock::get()?.unix_timestamp;
-        let lock_period_seconds = staker.lock_period_months as i64 * 30 * 24 * 60 * 60;
         
-        // Проверяем, истек ли период блокировки
+        // Истекшая эпоха закрывается до изменения стейка, чтобы ее итоги не включали эту операцию
+        roll_emission_epoch(staking_pool, &ctx.accounts.leaderboard, current_time);
+        
+        // Проверяем, истек ли период блокировки (автопродление не дает ему истечь)
         require!(
-            current_time >= staker.stake_time + lock_period_seconds,
+            current_time >= effective_unlock_at(staker, current_time),
             ErrorCode::LockPeriodNotExpired
         );
         
+        // Фиксируем rewards до уменьшения стейка: на время охлаждения они не начисляются
+        accrue_rewards_capped(staker, current_time);
+        settle_revenue(staking_pool, staker);
+        
         // Обновляем стейкинг
+        let old_amount = staker.amount;
         staker.amount = staker.amount.checked_sub(amount).unwrap();
+        staker.unbonding_amount = staker.unbonding_amount.checked_add(amount).unwrap();
+        sync_revenue_debt(staking_pool, staker);
+        update_staker_count(staking_pool, old_amount, staker.amount);
+        update_leaderboard(&mut ctx.accounts.leaderboard, staker.key(), staker.amount);
+        checkpoint_voting_power(staking_pool, staker, current_time);
         staking_pool.total_staked = staking_pool.total_staked.checked_sub(amount).unwrap();
+        staking_pool.total_unbonding = staking_pool.total_unbonding.checked_add(amount).unwrap();
         
-        emit!(UnstakeEvent {
+        ticket.staker = staker.key();
+        ticket.owner = ctx.accounts.position_token_account.owner;
+        ticket.ticket_id = staker.next_ticket_id;
+        ticket.amount = amount;
+        ticket.requested_at = current_time;
+        ticket.unlock_time = current_time.checked_add(staking_pool.unbonding_cooldown).unwrap();
+        
+        staker.next_ticket_id = staker.next_ticket_id.checked_add(1).unwrap();
+        
+        emit!(UnstakeRequestedEvent {
             staker: staker.key(),
+            actor: authority.key(),
+            ticket: ticket.key(),
             amount,
-            timestamp: Clock::get()?.unix_timestamp,
+            unlock_time: ticket.unlock_time,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Вывод средств по тикету после периода охлаждения (в аварийном режиме сразу)
+    pub fn withdraw_unbonded(ctx: Context<WithdrawUnbonded>) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
+        let ticket = &ctx.accounts.ticket;
+        
+        // В аварийном режиме тикеты выводятся без ожидания
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            staking_pool.emergency_mode || current_time >= ticket.unlock_time,
+            ErrorCode::UnbondingNotFinished
+        );
+        
+        let seeds = &[b"staking".as_ref(), &[ctx.bumps.staking_pool]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.staker_token_account.to_account_info(),
+                    authority: staking_pool.to_account_info(),
+                },
+                signer,
+            ),
+            ticket.amount,
+        )?;
+        
+        staker.unbonding_amount = staker.unbonding_amount.checked_sub(ticket.amount).unwrap();
+        staking_pool.total_unbonding = staking_pool.total_unbonding.checked_sub(ticket.amount).unwrap();
+        
+        emit!(UnstakeEvent {
+            staker: staker.key(),
+            actor: ctx.accounts.authority.key(),
+            amount: ticket.amount,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Отмена вывода: сумма тикета возвращается в стейк
+    pub fn cancel_unbond(ctx: Context<CancelUnbond>) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
+        let ticket = &ctx.accounts.ticket;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        // Истекшая эпоха закрывается до изменения стейка, чтобы ее итоги не включали эту операцию
+        roll_emission_epoch(staking_pool, &ctx.accounts.leaderboard, current_time);
+        
+        // Начисление на возвращенную сумму начинается с текущего момента
+        accrue_rewards(staker, current_time)?;
+        settle_revenue(staking_pool, staker);
+        
+        let old_amount = staker.amount;
+        staker.amount = staker.amount.checked_add(ticket.amount).unwrap();
+        staker.unbonding_amount = staker.unbonding_amount.checked_sub(ticket.amount).unwrap();
+        sync_revenue_debt(staking_pool, staker);
+        update_staker_count(staking_pool, old_amount, staker.amount);
+        update_leaderboard(&mut ctx.accounts.leaderboard, staker.key(), staker.amount);
+        checkpoint_voting_power(staking_pool, staker, current_time);
+        staking_pool.total_staked = staking_pool.total_staked.checked_add(ticket.amount).unwrap();
+        staking_pool.total_unbonding = staking_pool.total_unbonding.checked_sub(ticket.amount).unwrap();
+        
+        emit!(UnbondCancelledEvent {
+            staker: staker.key(),
+            actor: ctx.accounts.authority.key(),
+            ticket: ticket.key(),
+            amount: ticket.amount,
+            timestamp: current_time,
         });
         
         Ok(())
     }
 
-    // Claim rewards
+    // Claim rewards: эмиссия NDT и доля доходов платформы
     pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
         let staking_pool = &mut ctx.accounts.staking_pool;
         let staker = &mut ctx.accounts.staker;
         let rewards_account = &mut ctx.accounts.rewards_account;
+        let rewards_vault = &ctx.accounts.rewards_vault;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
         
         let current_time = Clock::get()?.unix_timestamp;
-        let time_elapsed = current_time.checked_sub(staker.last_claim_time).unwrap();
         
         // Рассчитываем накопленные rewards
-        let rewards = staker.amount
-            .checked_mul(staker.apy as u64)
-            .unwrap()
-            .checked_mul(time_elapsed)
-            .unwrap()
-            .checked_div(365 * 24 * 60 * 60) // Год в секундах
-            .unwrap()
-            .checked_div(100) // APY в процентах
-            .unwrap();
+        accrue_rewards(staker, current_time)?;
+        settle_revenue(staking_pool, staker);
+        roll_emission_epoch(staking_pool, &ctx.accounts.leaderboard, current_time);
+        let rewards = staker.accrued_rewards;
+        let revenue_rewards = staker.revenue_owed;
+        
+        require!(rewards > 0 || revenue_rewards > 0, ErrorCode::NoRewardsToClaim);
+        
+        let payout = emission_payout(staking_pool, staker, rewards_vault.amount);
+        
+        require!(payout > 0 || revenue_rewards > 0, ErrorCode::RewardsBudgetExhausted);
+        
+        let seeds = &[b"staking".as_ref(), &[ctx.bumps.staking_pool]];
+        let signer = &[&seeds[..]];
+        
+        // Выплачиваем rewards из хранилища
+        if payout > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: rewards_vault.to_account_info(),
+                        to: rewards_account.to_account_info(),
+                        authority: staking_pool.to_account_info(),
+                    },
+                    signer,
+                ),
+                payout,
+            )?;
+            
+            record_emission(staking_pool, staker, &mut ctx.accounts.referrer_account, payout, rewards_vault.amount, current_time)?;
+        }
+        
+        // Выплачиваем долю доходов платформы
+        if revenue_rewards > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: ctx.accounts.revenue_vault.to_account_info(),
+                        to: ctx.accounts.revenue_account.to_account_info(),
+                        authority: staking_pool.to_account_info(),
+                    },
+                    signer,
+                ),
+                revenue_rewards,
+            )?;
+            
+            staker.revenue_owed = 0;
+            staking_pool.total_revenue_distributed = staking_pool.total_revenue_distributed.checked_add(revenue_rewards).unwrap();
+        }
+        
+        emit!(ClaimRewardsEvent {
+            staker: staker.key(),
+            actor: ctx.accounts.authority.key(),
+            rewards: payout,
+            revenue_rewards,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Реинвестирование rewards в стейк позиции (блокировка не меняется)
+    pub fn compound_rewards(ctx: Context<CompoundRewards>) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
+        let rewards_vault = &ctx.accounts.rewards_vault;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        
+        let current_time = Clock::get()?.unix_timestamp;
         
-        require!(rewards > 0, ErrorCode::NoRewardsToClaim);
+        // Истекшая эпоха закрывается до изменения стейка, чтобы ее итоги не включали эту операцию
+        roll_emission_epoch(staking_pool, &ctx.accounts.leaderboard, current_time);
+        
+        accrue_rewards(staker, current_time)?;
+        settle_revenue(staking_pool, staker);
+        
+        require!(staker.accrued_rewards > 0, ErrorCode::NoRewardsToClaim);
+        
+        let payout = emission_payout(staking_pool, staker, rewards_vault.amount);
+        require!(payout > 0, ErrorCode::RewardsBudgetExhausted);
         
-        // Выпускаем rewards
         let seeds = &[b"staking".as_ref(), &[ctx.bumps.staking_pool]];
         let signer = &[&seeds[..]];
         
-        anchor_spl::token::mint_to(
+        // Rewards переходят из хранилища наград в хранилище стейкинга
+        anchor_spl::token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::MintTo {
-                    mint: ctx.accounts.mint.to_account_info(),
-                    to: rewards_account.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: rewards_vault.to_account_info(),
+                    to: ctx.accounts.stake_vault.to_account_info(),
                     authority: staking_pool.to_account_info(),
                 },
+                signer,
             ),
-            signer,
-            rewards,
+            payout,
         )?;
         
-        // Обновляем счетчики
-        staker.last_claim_time = current_time;
-        staking_pool.total_rewards_distributed = staking_pool.total_rewards_distributed.checked_add(rewards).unwrap();
+        record_emission(staking_pool, staker, &mut ctx.accounts.referrer_account, payout, rewards_vault.amount, current_time)?;
         
-        emit!(ClaimRewardsEvent {
+        let old_amount = staker.amount;
+        staker.amount = staker.amount.checked_add(payout).unwrap();
+        sync_revenue_debt(staking_pool, staker);
+        update_staker_count(staking_pool, old_amount, staker.amount);
+        update_leaderboard(&mut ctx.accounts.leaderboard, staker.key(), staker.amount);
+        checkpoint_voting_power(staking_pool, staker, current_time);
+        staking_pool.total_staked = staking_pool.total_staked.checked_add(payout).unwrap();
+        
+        emit!(CompoundRewardsEvent {
             staker: staker.key(),
-            rewards,
+            actor: ctx.accounts.authority.key(),
+            amount: payout,
+            new_stake: staker.amount,
             timestamp: current_time,
         });
         
         Ok(())
     }
 
-    // Обновление уровня стейкинга
-    pub fn update_staking_level(ctx: Context<UpdateStakingLevel>) -> Result<()> {
-        let staker = &mut ctx.accounts.staker;
+    // Назначение делегата с ограниченными правами (только держатель позиции).
+    // Делегирование действует, пока NFT позиции у выдавшего его держателя.
+    pub fn set_delegate(ctx: Context<SetDelegate>, delegate: Pubkey, scope: DelegateScope) -> Result<()> {
         let staking_pool = &ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
+        let authority = &ctx.accounts.authority;
         
-        let old_level = staker.level;
-        let new_level = calculate_staking_level(staker.total_staked, staking_pool);
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        require!(scope != DelegateScope::None, ErrorCode::InvalidDelegateScope);
+        require!(delegate != authority.key(), ErrorCode::InvalidDelegate);
         
-        if old_level != new_level {
-            staker.level = new_level;
-            
-            emit!(StakingLevelUpdatedEvent {
-                staker: staker.key(),
-                old_level,
-                new_level,
-                timestamp: Clock::get()?.unix_timestamp,
-            });
-        }
+        staker.delegate = Some(delegate);
+        staker.delegate_scope = scope;
+        staker.delegate_granter = authority.key();
+        
+        emit!(DelegateUpdatedEvent {
+            staker: staker.key(),
+            holder: authority.key(),
+            delegate: Some(delegate),
+            scope,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
         
         Ok(())
     }
 
-    // Обновление APY для уровня
-    pub fn update_tier_apy(
-        ctx: Context<UpdateTierApy>,
-        tier: StakingTier,
-        new_apy: u8,
-    ) -> Result<()> {
-        let staking_pool = &mut ctx.accounts.staking_pool;
+    // Отзыв делегата держателем позиции
+    pub fn revoke_delegate(ctx: Context<SetDelegate>) -> Result<()> {
+        let staker = &mut ctx.accounts.staker;
         let authority = &ctx.accounts.authority;
         
-        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        require!(staker.delegate.is_some(), ErrorCode::InvalidDelegate);
         
-        match tier {
-            StakingTier::Bronze => staking_pool.bronze_base_apy = new_apy,
-            StakingTier::Silver => staking_pool.silver_base_apy = new_apy,
-            StakingTier::Gold => staking_pool.gold_base_apy = new_apy,
-        }
+        staker.delegate = None;
+        staker.delegate_scope = DelegateScope::None;
+        staker.delegate_granter = Pubkey::default();
         
-        emit!(TierApyUpdatedEvent {
-            tier,
-            new_apy,
+        emit!(DelegateUpdatedEvent {
+            staker: staker.key(),
+            holder: authority.key(),
+            delegate: None,
+            scope: DelegateScope::None,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    // Обновление порогов уровней
-    pub fn update_tier_thresholds(
-        ctx: Context<UpdateTierThresholds>,
-        bronze_threshold: u64,
-        silver_threshold: u64,
-        gold_threshold: u64,
-    ) -> Result<()> {
+    // Аварийный режим: доступен только emergency_withdraw
+    pub fn set_emergency_mode(ctx: Context<SetEmergencyMode>, enabled: bool) -> Result<()> {
         let staking_pool = &mut ctx.accounts.staking_pool;
         let authority = &ctx.accounts.authority;
         
         require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
         
-        staking_pool.bronze_threshold = bronze_threshold;
-        staking_pool.silver_threshold = silver_threshold;
-        staking_pool.gold_threshold = gold_threshold;
+        staking_pool.emergency_mode = enabled;
         
-        emit!(TierThresholdsUpdatedEvent {
-            bronze_threshold,
-            silver_threshold,
-            gold_threshold,
+        emit!(EmergencyModeToggledEvent {
+            enabled,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    // Получение информации о стейкинге
-    pub fn get_staking_info(ctx: Context<GetStakingInfo>) -> Result<StakingInfo> {
-        let staker = &ctx.accounts.staker;
-        let current_time = Clock::get()?.unix_timestamp;
+    // Аварийный вывод: возвращает основную сумму без учета блокировки, rewards сгорают.
+    // Не выполняет расчет rewards, поэтому работает даже при сбое их математики.
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
         
-        let time_elapsed = current_time.checked_sub(staker.last_claim_time).unwrap();
-        let pending_rewards = staker.amount
-            .checked_mul(staker.apy as u64)
-            .unwrap()
-            .checked_mul(time_elapsed)
-            .unwrap()
-            .checked_div(365 * 24 * 60 * 60)
-            .unwrap()
-            .checked_div(100)
-            .unwrap();
+        require!(staking_pool.emergency_mode, ErrorCode::EmergencyModeInactive);
         
-        let lock_period_remaining = if current_time < staker.stake_time + (staker.lock_period_months as i64 * 30 * 24 * 60 * 60) {
-            staker.stake_time + (staker.lock_period_months as i64 * 30 * 24 * 60 * 60) - current_time
-        } else {
-            0
-        };
+        let amount = staker.amount;
+        require!(amount > 0, ErrorCode::InsufficientStake);
         
-        Ok(StakingInfo {
-            total_staked: staker.amount,
-            apy: staker.apy,
-            level: staker.level,
-            lock_period_months: staker.lock_period_months,
-            pending_rewards,
-            lock_period_remaining,
-        })
-    }
-}
-
-// Helper функции
-fn get_tier_multiplier(total_staked: u64) -> u64 {
-    if total_staked >= 50_000_000_000 { // Gold tier
-        200 // 2x multiplier
-    } else if total_staked >= 5_000_000_000 { // Silver tier
-        150 // 1.5x multiplier
-    } else if total_staked >= 500_000_000 { // Bronze tier
-        120 // 1.2x multiplier
-    } else {
-        100 // 1x multiplier
+        let seeds = &[b"staking".as_ref(), &[ctx.bumps.staking_pool]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.staker_token_account.to_account_info(),
+                    authority: staking_pool.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        // Истекшая эпоха закрывается до изменения стейка, чтобы ее итоги не включали эту операцию
+        roll_emission_epoch(staking_pool, &ctx.accounts.leaderboard, current_time);
+        
+        let forfeited_rewards = staker.accrued_rewards;
+        
+        staker.amount = 0;
+        staker.accrued_rewards = 0;
+        staker.revenue_owed = 0;
+        staker.revenue_debt = 0;
+        staker.last_claim_time = current_time;
+        staking_pool.total_staked = staking_pool.total_staked.saturating_sub(amount);
+        update_staker_count(staking_pool, amount, 0);
+        update_leaderboard(&mut ctx.accounts.leaderboard, staker.key(), 0);
+        checkpoint_voting_power(staking_pool, staker, current_time);
+        
+        emit!(EmergencyWithdrawEvent {
+            staker: staker.key(),
+            amount,
+            forfeited_rewards,
+            timestamp: current_time,
+        });
+        
+        Ok(())
     }
-}
 
-fn get_time_multiplier(lock_period_months: u8) -> u64 {
-    if lock_period_months >= 12 { // 12+ месяцев
-        200 // 2x multiplier
-    } else if lock_period_months >= 6 { // 6+ месяцев
-        150 // 1.5x multiplier
-    } else if lock_period_months >= 3 { // 3+ месяца
-        120 // 1.2x multiplier
-    } else {
-        100 // 1x multiplier
+    // Настройка реферальной программы
+    pub fn configure_referrals(
+        ctx: Context<ConfigureReferrals>,
+        referral_bps: u16,
+        referral_budget: u64,
+    ) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        require!(referral_bps <= MAX_REFERRAL_BPS, ErrorCode::ReferralBpsTooHigh);
+        
+        staking_pool.referral_bps = referral_bps;
+        staking_pool.referral_budget = referral_budget;
+        
+        emit!(ReferralsConfiguredEvent {
+            referral_bps,
+            referral_budget,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
     }
-}
 
-fn get_base_apy(total_staked: u64, staking_pool: &Account<StakingPool>) -> u64 {
-    if total_staked >= staking_pool.gold_threshold {
-        staking_pool.gold_base_apy as u64
-    } else if total_staked >= staking_pool.silver_threshold {
-        staking_pool.silver_base_apy as u64
-    } else if total_staked >= staking_pool.bronze_threshold {
-        staking_pool.bronze_base_apy as u64
-    } else {
-        5 // Default APY
+    // Регистрация реферера
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let staking_pool = &ctx.accounts.staking_pool;
+        let referrer_account = &mut ctx.accounts.referrer_account;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        
+        referrer_account.referrer = ctx.accounts.referrer.key();
+        referrer_account.accrued_rewards = 0;
+        referrer_account.total_claimed = 0;
+        referrer_account.referee_count = 0;
+        
+        Ok(())
+    }
+
+    // Вывод реферальных rewards
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let referrer_account = &mut ctx.accounts.referrer_account;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        
+        require!(referrer_account.accrued_rewards > 0, ErrorCode::NoRewardsToClaim);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        roll_emission_epoch(staking_pool, &ctx.accounts.leaderboard, current_time);
+        
+        // Реферальные выплаты идут из той же эмиссии и учитываются в лимите эпохи
+        let epoch_remaining = staking_pool.epoch_emission_cap.saturating_sub(staking_pool.epoch_emitted);
+        let amount = referrer_account.accrued_rewards
+            .min(epoch_remaining)
+            .min(ctx.accounts.rewards_vault.amount);
+        require!(amount > 0, ErrorCode::RewardsBudgetExhausted);
+        
+        let seeds = &[b"staking".as_ref(), &[ctx.bumps.staking_pool]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.rewards_vault.to_account_info(),
+                    to: ctx.accounts.rewards_account.to_account_info(),
+                    authority: staking_pool.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        referrer_account.accrued_rewards = referrer_account.accrued_rewards.checked_sub(amount).unwrap();
+        referrer_account.total_claimed = referrer_account.total_claimed.checked_add(amount).unwrap();
+        staking_pool.epoch_emitted = staking_pool.epoch_emitted.checked_add(amount).unwrap();
+        staking_pool.total_rewards_distributed = staking_pool.total_rewards_distributed.checked_add(amount).unwrap();
+        
+        emit!(ReferralRewardsClaimedEvent {
+            referrer: referrer_account.referrer,
+            amount,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Создание хранилища доходов платформы (USDC или любой SPL mint)
+    pub fn initialize_revenue_vault(ctx: Context<InitializeRevenueVault>) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        
+        staking_pool.revenue_mint = ctx.accounts.revenue_mint.key();
+        staking_pool.revenue_vault = ctx.accounts.revenue_vault.key();
+        staking_pool.revenue_per_share = 0;
+        
+        Ok(())
+    }
+
+    // Внесение доходов платформы (например, комиссий tracknft::buy_track)
+    pub fn deposit_revenue(ctx: Context<DepositRevenue>, amount: u64) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let depositor = &ctx.accounts.depositor;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        
+        require!(amount > 0, ErrorCode::InvalidRevenueAmount);
+        require!(staking_pool.total_staked > 0, ErrorCode::NoStakeForRevenue);
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.revenue_vault.to_account_info(),
+                    authority: depositor.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        // Распределяем пропорционально застейканному весу
+        let increment = (amount as u128)
+            .checked_mul(REVENUE_PRECISION)
+            .unwrap()
+            .checked_div(staking_pool.total_staked as u128)
+            .unwrap();
+        staking_pool.revenue_per_share = staking_pool.revenue_per_share.checked_add(increment).unwrap();
+        staking_pool.total_revenue_deposited = staking_pool.total_revenue_deposited.checked_add(amount).unwrap();
+        
+        emit!(RevenueDepositedEvent {
+            depositor: depositor.key(),
+            amount,
+            revenue_per_share: staking_pool.revenue_per_share,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Создание хранилища rewards
+    pub fn initialize_rewards_vault(
+        ctx: Context<InitializeRewardsVault>,
+        epoch_duration: i64,
+        epoch_emission_cap: u64,
+    ) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        require!(epoch_duration > 0, ErrorCode::InvalidEpochDuration);
+        
+        staking_pool.rewards_vault = ctx.accounts.rewards_vault.key();
+        staking_pool.epoch_duration = epoch_duration;
+        staking_pool.epoch_emission_cap = epoch_emission_cap;
+        staking_pool.epoch_start = Clock::get()?.unix_timestamp;
+        staking_pool.epoch_emitted = 0;
+        
+        emit!(EmissionCapUpdatedEvent {
+            epoch_duration,
+            epoch_emission_cap,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Пополнение бюджета rewards
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
+        let staking_pool = &ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.rewards_vault.to_account_info(),
+                    authority: authority.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        emit!(RewardsFundedEvent {
+            amount,
+            vault_balance: ctx.accounts.rewards_vault.amount.checked_add(amount).unwrap(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Обновление лимита эмиссии за эпоху
+    pub fn update_emission_cap(
+        ctx: Context<UpdateEmissionCap>,
+        epoch_duration: i64,
+        epoch_emission_cap: u64,
+    ) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        require!(epoch_duration > 0, ErrorCode::InvalidEpochDuration);
+        
+        staking_pool.epoch_duration = epoch_duration;
+        staking_pool.epoch_emission_cap = epoch_emission_cap;
+        
+        emit!(EmissionCapUpdatedEvent {
+            epoch_duration,
+            epoch_emission_cap,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Создание таблицы лидеров
+    pub fn initialize_leaderboard(ctx: Context<InitializeLeaderboard>) -> Result<()> {
+        let staking_pool = &ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        
+        ctx.accounts.leaderboard.entries = Vec::new();
+        
+        Ok(())
+    }
+
+    // Снимок пула и таблицы лидеров для эпохи, закрытой последним переходом.
+    // Может вызвать любой после окончания эпохи; эпоха закрывается здесь или лениво
+    // при любой операции, меняющей стейк или выплачивающей rewards.
+    pub fn roll_epoch(ctx: Context<RollEpoch>, epoch: u64) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let snapshot = &mut ctx.accounts.epoch_snapshot;
+        
+        require!(staking_pool.epoch_duration > 0, ErrorCode::InvalidEpochDuration);
+        
+        roll_emission_epoch(staking_pool, &ctx.accounts.leaderboard, Clock::get()?.unix_timestamp);
+        
+        require!(epoch < staking_pool.current_epoch, ErrorCode::EpochNotFinished);
+        require!(epoch >= staking_pool.last_epoch_first, ErrorCode::EpochSnapshotUnavailable);
+        
+        // Эпохи, закрытые одним переходом, имеют равную длину; выплаты были только в первой
+        let closed_epochs = staking_pool.current_epoch.checked_sub(staking_pool.last_epoch_first).unwrap();
+        let epoch_length = staking_pool.last_epoch_end
+            .checked_sub(staking_pool.last_epoch_start)
+            .unwrap()
+            .checked_div(closed_epochs as i64)
+            .unwrap();
+        let offset = epoch.checked_sub(staking_pool.last_epoch_first).unwrap();
+        
+        snapshot.epoch = epoch;
+        snapshot.start_time = staking_pool.last_epoch_start
+            .checked_add(epoch_length.checked_mul(offset as i64).unwrap())
+            .unwrap();
+        snapshot.end_time = snapshot.start_time.checked_add(epoch_length).unwrap();
+        snapshot.total_staked = staking_pool.last_epoch_total_staked;
+        snapshot.rewards_paid = if offset == 0 { staking_pool.last_epoch_emitted } else { 0 };
+        snapshot.staker_count = staking_pool.last_epoch_staker_count;
+        snapshot.leaderboard = staking_pool.last_epoch_leaderboard.clone();
+        
+        Ok(())
+    }
+
+    // Создание хранилища стейкинга
+    pub fn initialize_stake_vault(
+        ctx: Context<InitializeStakeVault>,
+        unbonding_cooldown: i64,
+    ) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        require!(unbonding_cooldown >= 0, ErrorCode::InvalidUnbondingCooldown);
+        
+        staking_pool.stake_vault = ctx.accounts.stake_vault.key();
+        staking_pool.unbonding_cooldown = unbonding_cooldown;
+        
+        emit!(UnbondingCooldownUpdatedEvent {
+            unbonding_cooldown,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Обновление периода охлаждения
+    pub fn update_unbonding_cooldown(
+        ctx: Context<UpdateUnbondingCooldown>,
+        unbonding_cooldown: i64,
+    ) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        require!(unbonding_cooldown >= 0, ErrorCode::InvalidUnbondingCooldown);
+        
+        staking_pool.unbonding_cooldown = unbonding_cooldown;
+        
+        emit!(UnbondingCooldownUpdatedEvent {
+            unbonding_cooldown,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Обновление уровня стейкинга
+    pub fn update_staking_level(ctx: Context<UpdateStakingLevel>) -> Result<()> {
+        let staker = &mut ctx.accounts.staker;
+        let staking_pool = &ctx.accounts.staking_pool;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        
+        let old_level = staker.level;
+        let new_level = calculate_staking_level(staker.total_staked, staking_pool);
+        
+        if old_level != new_level {
+            staker.level = new_level;
+            
+            emit!(StakingLevelUpdatedEvent {
+                staker: staker.key(),
+                old_level,
+                new_level,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        
+        Ok(())
     }
+
+    // Обновление APY для уровня
+    pub fn update_tier_apy(
+        ctx: Context<UpdateTierApy>,
+        tier: StakingTier,
+        new_apy: u8,
+    ) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        
+        match tier {
+            StakingTier::Bronze => staking_pool.bronze_base_apy = new_apy,
+            StakingTier::Silver => staking_pool.silver_base_apy = new_apy,
+            StakingTier::Gold => staking_pool.gold_base_apy = new_apy,
+        }
+        
+        emit!(TierApyUpdatedEvent {
+            tier,
+            new_apy,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Обновление порогов уровней
+    pub fn update_tier_thresholds(
+        ctx: Context<UpdateTierThresholds>,
+        bronze_threshold: u64,
+        silver_threshold: u64,
+        gold_threshold: u64,
+    ) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        
+        staking_pool.bronze_threshold = bronze_threshold;
+        staking_pool.silver_threshold = silver_threshold;
+        staking_pool.gold_threshold = gold_threshold;
+        
+        emit!(TierThresholdsUpdatedEvent {
+            bronze_threshold,
+            silver_threshold,
+            gold_threshold,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Настройка слэшинга репортеров
+    pub fn configure_slashing(
+        ctx: Context<ConfigureSlashing>,
+        slasher: Pubkey,
+        min_reporter_stake: u64,
+    ) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == staking_pool.authority, ErrorCode::Unauthorized);
+        
+        staking_pool.slasher = slasher;
+        staking_pool.treasury = ctx.accounts.treasury.key();
+        staking_pool.min_reporter_stake = min_reporter_stake;
+        
+        emit!(SlashingConfiguredEvent {
+            slasher,
+            treasury: staking_pool.treasury,
+            min_reporter_stake,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Регистрация позиции как залога репортера данных
+    pub fn register_reporter(ctx: Context<RegisterReporter>) -> Result<()> {
+        let staking_pool = &ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
+        let reporter = &mut ctx.accounts.reporter;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        
+        require!(staker.amount >= staking_pool.min_reporter_stake, ErrorCode::ReporterStakeTooLow);
+        
+        reporter.staker = staker.key();
+        reporter.bonded_at = Clock::get()?.unix_timestamp;
+        reporter.total_slashed = 0;
+        reporter.slash_count = 0;
+        reporter.exit_requested_at = 0;
+        staker.is_reporter = true;
+        
+        emit!(ReporterRegisteredEvent {
+            staker: staker.key(),
+            reporter: reporter.key(),
+            bonded_amount: staker.amount,
+            timestamp: reporter.bonded_at,
+        });
+        
+        Ok(())
+    }
+
+    // Запрос на снятие роли репортера; до конца задержки залог остается под слэшингом
+    pub fn request_reporter_exit(ctx: Context<RequestReporterExit>) -> Result<()> {
+        let staking_pool = &ctx.accounts.staking_pool;
+        let reporter = &mut ctx.accounts.reporter;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        require!(reporter.exit_requested_at == 0, ErrorCode::ReporterExitAlreadyRequested);
+        
+        reporter.exit_requested_at = Clock::get()?.unix_timestamp;
+        
+        emit!(ReporterExitRequestedEvent {
+            staker: ctx.accounts.staker.key(),
+            reporter: reporter.key(),
+            exit_available_at: reporter.exit_requested_at.checked_add(REPORTER_EXIT_DELAY).unwrap(),
+            timestamp: reporter.exit_requested_at,
+        });
+        
+        Ok(())
+    }
+
+    // Снятие роли репортера после задержки
+    pub fn deregister_reporter(ctx: Context<DeregisterReporter>) -> Result<()> {
+        let staking_pool = &ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
+        let reporter = &ctx.accounts.reporter;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        require!(reporter.exit_requested_at > 0, ErrorCode::ReporterExitNotRequested);
+        require!(
+            Clock::get()?.unix_timestamp >= reporter.exit_requested_at.checked_add(REPORTER_EXIT_DELAY).unwrap(),
+            ErrorCode::ReporterExitDelayNotPassed
+        );
+        
+        staker.is_reporter = false;
+        
+        emit!(ReporterDeregisteredEvent {
+            staker: staker.key(),
+            reporter: ctx.accounts.reporter.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Слэшинг репортера, уличенного в фальсификации данных.
+    // slasher может быть PDA программы royalty-distribution, подписывающим через CPI.
+    pub fn slash(
+        ctx: Context<Slash>,
+        amount: u64,
+        reason: String,
+        dispute: Pubkey,
+    ) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
+        let reporter = &mut ctx.accounts.reporter;
+        let slasher = &ctx.accounts.slasher;
+        
+        require!(slasher.key() == staking_pool.slasher, ErrorCode::Unauthorized);
+        require!(reason.len() <= MAX_SLASH_REASON_LEN, ErrorCode::SlashReasonTooLong);
+        require!(amount > 0 && staker.amount >= amount, ErrorCode::InsufficientStake);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        // Истекшая эпоха закрывается до изменения стейка, чтобы ее итоги не включали эту операцию
+        roll_emission_epoch(staking_pool, &ctx.accounts.leaderboard, current_time);
+        
+        // Фиксируем rewards по сумме до слэшинга
+        accrue_rewards_capped(staker, current_time);
+        settle_revenue(staking_pool, staker);
+        
+        let old_amount = staker.amount;
+        staker.amount = staker.amount.checked_sub(amount).unwrap();
+        staking_pool.total_staked = staking_pool.total_staked.checked_sub(amount).unwrap();
+        sync_revenue_debt(staking_pool, staker);
+        update_staker_count(staking_pool, old_amount, staker.amount);
+        update_leaderboard(&mut ctx.accounts.leaderboard, staker.key(), staker.amount);
+        checkpoint_voting_power(staking_pool, staker, current_time);
+        
+        // Переводим срезанную часть в казну
+        let seeds = &[b"staking".as_ref(), &[ctx.bumps.staking_pool]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: staking_pool.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        reporter.total_slashed = reporter.total_slashed.checked_add(amount).unwrap();
+        reporter.slash_count = reporter.slash_count.checked_add(1).unwrap();
+        
+        emit!(SlashEvent {
+            staker: staker.key(),
+            reporter: reporter.key(),
+            slasher: slasher.key(),
+            amount,
+            remaining_stake: staker.amount,
+            reason,
+            dispute,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Пересчитывает вклад позиции в общее число голосов (например, после смены уровня)
+    pub fn checkpoint_voting(ctx: Context<CheckpointVoting>) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        
+        checkpoint_voting_power(staking_pool, staker, Clock::get()?.unix_timestamp);
+        
+        Ok(())
+    }
+
+    // Включение/выключение автопродления блокировки.
+    // После выключения начинается обычный отсчет полного периода блокировки.
+    pub fn set_auto_renew(ctx: Context<SetAutoRenew>, enabled: bool) -> Result<()> {
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let staker = &mut ctx.accounts.staker;
+        
+        require!(!staking_pool.emergency_mode, ErrorCode::EmergencyModeActive);
+        require!(staker.auto_renew != enabled, ErrorCode::AutoRenewUnchanged);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        if enabled {
+            require!(staker.lock_duration > 0, ErrorCode::InvalidLockDuration);
+            staker.unlock_at = effective_unlock_at(staker, current_time);
+            staker.auto_renew = true;
+        } else {
+            staker.unlock_at = effective_unlock_at(staker, current_time)
+                .max(current_time.checked_add(staker.lock_duration).unwrap());
+            staker.auto_renew = false;
+        }
+        
+        checkpoint_voting_power(staking_pool, staker, current_time);
+        
+        emit!(AutoRenewUpdatedEvent {
+            staker: staker.key(),
+            actor: ctx.accounts.authority.key(),
+            enabled,
+            unlock_at: staker.unlock_at,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Сила голоса позиции и общая сила голосов для кворума
+    pub fn get_voting_power(ctx: Context<GetVotingPower>) -> Result<VotingPowerInfo> {
+        let staking_pool = &ctx.accounts.staking_pool;
+        let staker = &ctx.accounts.staker;
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        Ok(VotingPowerInfo {
+            voting_power: voting_power(staker, current_time),
+            total_voting_power: total_voting_power(staking_pool, current_time),
+            unlock_time: if staker.auto_renew {
+                effective_unlock_at(staker, current_time)
+            } else {
+                vote_unlock_time(staker)
+            },
+            timestamp: current_time,
+        })
+    }
+
+    // Уровень и бонусы держателя позиции; вызывается другими программами через CPI.
+    // Подпись держателя не нужна: держатель - владелец position_token_account,
+    // вызывающая программа сверяет его с покупателем или слушателем.
+    pub fn get_staker_perks(ctx: Context<GetStakerPerks>) -> Result<StakerPerks> {
+        Ok(perks::perks_for(&ctx.accounts.staking_pool, &ctx.accounts.staker))
+    }
+
+    // Получение информации о любой позиции (без подписи владельца)
+    pub fn get_staking_info(ctx: Context<GetStakingInfo>) -> Result<StakingInfo> {
+        let staking_pool = &ctx.accounts.staking_pool;
+        let staker = &ctx.accounts.staker;
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        let time_elapsed = current_time.checked_sub(staker.last_claim_time).unwrap();
+        let pending_rewards = staker.accrued_rewards
+            .checked_add(calculate_rewards(staker.amount, staker.apy, time_elapsed)?)
+            .unwrap();
+        
+        let pending_revenue = (staker.amount as u128)
+            .checked_mul(staking_pool.revenue_per_share)
+            .unwrap()
+            .checked_div(REVENUE_PRECISION)
+            .unwrap()
+            .checked_sub(staker.revenue_debt)
+            .unwrap() as u64;
+        
+        let unlock_at = effective_unlock_at(staker, current_time);
+        let lock_period_remaining = if current_time < unlock_at {
+            unlock_at - current_time
+        } else {
+            0
+        };
+        
+        // Прогноз rewards к моменту разблокировки при текущем APY
+        let projected_rewards_at_unlock = pending_rewards
+            .checked_add(calculate_rewards(staker.amount, staker.apy, lock_period_remaining)?)
+            .unwrap();
+        
+        // Уровень считается по текущей сумме позиции, как в perks::perks_for
+        let level = perks::perks_for(staking_pool, staker).tier;
+        let next_level_threshold = match level {
+            StakingTier::Bronze => Some(staking_pool.silver_threshold),
+            StakingTier::Silver => Some(staking_pool.gold_threshold),
+            StakingTier::Gold => None,
+        };
+        
+        Ok(StakingInfo {
+            total_staked: staker.amount,
+            apy: staker.apy,
+            level,
+            lock_duration: staker.lock_duration,
+            unlock_at,
+            auto_renew: staker.auto_renew,
+            pending_rewards,
+            lock_period_remaining,
+            pending_revenue: staker.revenue_owed.checked_add(pending_revenue).unwrap(),
+            unbonding_amount: staker.unbonding_amount,
+            projected_rewards_at_unlock,
+            next_level_threshold,
+            base_apy: get_base_apy(staker.amount, staking_pool),
+            tier_multiplier: get_tier_multiplier(staker.amount),
+            time_multiplier: staker.time_multiplier,
+            pool_total_staked: staking_pool.total_staked,
+            pool_total_rewards_distributed: staking_pool.total_rewards_distributed,
+            pool_staker_count: staking_pool.staker_count,
+        })
+    }
+}
+
+// Интерфейс чтения бонусов для других программ (tracknft и т.д.).
+// Подключение: staking = { features = ["cpi"] }; фича cpi объявляется в манифесте
+// программы, который собирается вне этого дерева.
+pub mod perks {
+    use super::*;
+    
+    pub const MARKETPLACE_DISCOUNT: u32 = 1 << 0; // Bronze+
+    pub const LISTENER_BOOST: u32 = 1 << 1; // Silver+
+    pub const EARLY_ACCESS: u32 = 1 << 2; // Gold
+    
+    // Проверяет владельца и PDA пула и позиции, а также держателя NFT позиции.
+    // Данные клиента не используются: все значения читаются из аккаунтов staking.
+    pub fn read_staker_perks(
+        staking_pool: &AccountInfo,
+        staker: &AccountInfo,
+        position_token_account: &AccountInfo,
+        holder: &Pubkey,
+    ) -> Result<StakerPerks> {
+        let (pool_address, _) = Pubkey::find_program_address(&[b"staking"], &crate::ID);
+        require_keys_eq!(staking_pool.key(), pool_address, ErrorCode::InvalidStakerAccount);
+        require_keys_eq!(*staking_pool.owner, crate::ID, ErrorCode::InvalidStakerAccount);
+        let pool_data = StakingPool::try_deserialize(&mut &staking_pool.try_borrow_data()?[..])?;
+        
+        require_keys_eq!(*staker.owner, crate::ID, ErrorCode::InvalidStakerAccount);
+        let staker_data = Staker::try_deserialize(&mut &staker.try_borrow_data()?[..])?;
+        let (staker_address, _) = Pubkey::find_program_address(
+            &[b"staker", staker_data.position_mint.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(staker.key(), staker_address, ErrorCode::InvalidStakerAccount);
+        
+        require_keys_eq!(*position_token_account.owner, Token::id(), ErrorCode::NotPositionHolder);
+        let position = TokenAccount::try_deserialize(&mut &position_token_account.try_borrow_data()?[..])?;
+        require!(
+            position.mint == staker_data.position_mint
+                && position.owner == *holder
+                && position.amount == 1,
+            ErrorCode::NotPositionHolder
+        );
+        
+        Ok(perks_for(&pool_data, &staker_data))
+    }
+    
+    // Уровень определяется текущей суммой позиции и порогами пула
+    pub fn perks_for(staking_pool: &StakingPool, staker: &Staker) -> StakerPerks {
+        let (tier, perks) = if staker.amount >= staking_pool.gold_threshold {
+            (StakingTier::Gold, MARKETPLACE_DISCOUNT | LISTENER_BOOST | EARLY_ACCESS)
+        } else if staker.amount >= staking_pool.silver_threshold {
+            (StakingTier::Silver, MARKETPLACE_DISCOUNT | LISTENER_BOOST)
+        } else if staker.amount >= staking_pool.bronze_threshold {
+            (StakingTier::Bronze, MARKETPLACE_DISCOUNT)
+        } else {
+            (StakingTier::Bronze, 0)
+        };
+        
+        StakerPerks {
+            tier,
+            perks,
+            amount: staker.amount,
+        }
+    }
+}
+
+// Точность накопителя доходов на единицу стейка
+const REVENUE_PRECISION: u128 = 1_000_000_000_000;
+
+// Максимальная доля реферера (20%)
+const MAX_REFERRAL_BPS: u16 = 2_000;
+
+// Максимальная длина причины слэшинга
+const MAX_SLASH_REASON_LEN: usize = 200;
+
+// Задержка снятия роли репортера (залог остается под слэшингом)
+const REPORTER_EXIT_DELAY: i64 = 7 * 24 * 60 * 60;
+
+// Блокировка, дающая полную силу голоса (12 месяцев)
+const MAX_VOTE_LOCK_SECONDS: i64 = 365 * 24 * 60 * 60;
+
+// Окончания блокировок для голосования округляются до недели
+const VOTE_WEEK_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+// Недельные корзины истечения (покрывают MAX_VOTE_LOCK_SECONDS)
+const VE_EXPIRY_WEEKS: usize = 54;
+
+// Максимальное число вариантов блокировки
+const MAX_LOCK_OPTIONS: usize = 8;
+
+// Размер таблицы лидеров
+const LEADERBOARD_SIZE: usize = 10;
+
+// Helper функции
+fn mint_position_nft<'info>(
+    token_program: &Program<'info, Token>,
+    position_mint: &Account<'info, Mint>,
+    position_token_account: &Account<'info, TokenAccount>,
+    staking_pool: &Account<'info, StakingPool>,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    anchor_spl::token::mint_to(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            anchor_spl::token::MintTo {
+                mint: position_mint.to_account_info(),
+                to: position_token_account.to_account_info(),
+                authority: staking_pool.to_account_info(),
+            },
+            signer,
+        ),
+        1,
+    )?;
+    
+    // Фиксируем supply = 1
+    anchor_spl::token::set_authority(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            anchor_spl::token::SetAuthority {
+                current_authority: staking_pool.to_account_info(),
+                account_or_mint: position_mint.to_account_info(),
+            },
+            signer,
+        ),
+        anchor_spl::token::spl_token::instruction::AuthorityType::MintTokens,
+        None,
+    )
+}
+
+fn calculate_apy(staking_pool: &Account<StakingPool>, total_staked: u64, time_multiplier: u64) -> u64 {
+    let tier_multiplier = get_tier_multiplier(total_staked);
+    let base_apy = get_base_apy(total_staked, staking_pool);
+    
+    base_apy
+        .checked_mul(tier_multiplier)
+        .unwrap()
+        .checked_div(100)
+        .unwrap()
+        .checked_mul(time_multiplier)
+        .unwrap()
+        .checked_div(100)
+        .unwrap()
+}
+
+// Лучший множитель среди вариантов не длиннее заданной блокировки
+fn floor_time_multiplier(staking_pool: &StakingPool, lock_duration: i64) -> u64 {
+    staking_pool.lock_options
+        .iter()
+        .filter(|option| option.duration <= lock_duration)
+        .map(|option| option.multiplier)
+        .max()
+        .unwrap_or(100)
+}
+
+fn read_ndt_staking_account(account: &AccountInfo) -> Result<NdtStakingAccount> {
+    let data = account.try_borrow_data()?;
+    let discriminator = hash(b"account:StakingAccount").to_bytes();
+    
+    require!(
+        data.len() >= 8 && data[..8] == discriminator[..8],
+        ErrorCode::InvalidNdtStakingAccount
+    );
+    
+    let mut account_data: &[u8] = &data[8..];
+    NdtStakingAccount::deserialize(&mut account_data)
+        .map_err(|_| error!(ErrorCode::InvalidNdtStakingAccount))
+}
+
+// Считается в u128, результат, не влезающий в u64, возвращается ошибкой
+fn calculate_rewards(amount: u64, apy: u64, time_elapsed: i64) -> Result<u64> {
+    let rewards = (amount as u128)
+        .checked_mul(apy as u128)
+        .and_then(|value| value.checked_mul(time_elapsed.max(0) as u128))
+        .ok_or(ErrorCode::RewardsOverflow)?
+        .checked_div(365 * 24 * 60 * 60) // Год в секундах
+        .unwrap()
+        .checked_div(100) // APY в процентах
+        .unwrap();
+    
+    u64::try_from(rewards).map_err(|_| error!(ErrorCode::RewardsOverflow))
+}
+
+fn accrue_rewards(staker: &mut Staker, current_time: i64) -> Result<()> {
+    let time_elapsed = current_time.checked_sub(staker.last_claim_time).unwrap();
+    let rewards = calculate_rewards(staker.amount, staker.apy, time_elapsed)?;
+    
+    staker.accrued_rewards = staker.accrued_rewards
+        .checked_add(rewards)
+        .ok_or(ErrorCode::RewardsOverflow)?;
+    staker.last_claim_time = current_time;
+    
+    Ok(())
+}
+
+// Для выхода из позиции и слэшинга: переполнение rewards не должно их блокировать,
+// поэтому начисление ограничивается u64::MAX
+fn accrue_rewards_capped(staker: &mut Staker, current_time: i64) {
+    let time_elapsed = current_time.checked_sub(staker.last_claim_time).unwrap();
+    let rewards = calculate_rewards(staker.amount, staker.apy, time_elapsed).unwrap_or(u64::MAX);
+    
+    staker.accrued_rewards = staker.accrued_rewards.saturating_add(rewards);
+    staker.last_claim_time = current_time;
+}
+
+// Переносит долю доходов, накопленную с последнего изменения стейка, в revenue_owed
+fn settle_revenue(staking_pool: &StakingPool, staker: &mut Staker) {
+    let accumulated = (staker.amount as u128)
+        .checked_mul(staking_pool.revenue_per_share)
+        .unwrap()
+        .checked_div(REVENUE_PRECISION)
+        .unwrap();
+    let pending = accumulated.checked_sub(staker.revenue_debt).unwrap() as u64;
+    
+    staker.revenue_owed = staker.revenue_owed.checked_add(pending).unwrap();
+    staker.revenue_debt = accumulated;
+}
+
+// Вызывается после изменения staker.amount
+fn sync_revenue_debt(staking_pool: &StakingPool, staker: &mut Staker) {
+    staker.revenue_debt = (staker.amount as u128)
+        .checked_mul(staking_pool.revenue_per_share)
+        .unwrap()
+        .checked_div(REVENUE_PRECISION)
+        .unwrap();
+}
+
+// Окончание блокировки с учетом автопродления: истекший период
+// переносится вперед на целое число периодов
+fn effective_unlock_at(staker: &Staker, now: i64) -> i64 {
+    if !staker.auto_renew || staker.lock_duration <= 0 || now < staker.unlock_at {
+        return staker.unlock_at;
+    }
+    
+    let periods = (now - staker.unlock_at) / staker.lock_duration + 1;
+    staker.unlock_at
+        .checked_add(periods.checked_mul(staker.lock_duration).unwrap())
+        .unwrap()
+}
+
+// Блокировка дольше максимальной не увеличивает силу голоса.
+// Отсчет от начала текущего периода блокировки, округление вниз до недели.
+fn vote_unlock_time(staker: &Staker) -> i64 {
+    let lock_start = staker.unlock_at - staker.lock_duration;
+    let unlock_time = staker.unlock_at.min(lock_start + MAX_VOTE_LOCK_SECONDS);
+    unlock_time - unlock_time.rem_euclid(VOTE_WEEK_SECONDS)
+}
+
+fn ve_expiry_index(unlock_time: i64) -> usize {
+    unlock_time.div_euclid(VOTE_WEEK_SECONDS).rem_euclid(VE_EXPIRY_WEEKS as i64) as usize
+}
+
+// Недели, истекшие после последнего обновления агрегата и не позже now
+fn ve_expired_weeks(staking_pool: &StakingPool, now: i64) -> std::ops::RangeInclusive<i64> {
+    let last_week = staking_pool.ve_last_update.div_euclid(VOTE_WEEK_SECONDS);
+    let now_week = now.div_euclid(VOTE_WEEK_SECONDS);
+    (last_week + 1)..=now_week.min(last_week + VE_EXPIRY_WEEKS as i64)
+}
+
+// Автопродляемая блокировка не убывает: сила голоса фиксирована на полном периоде
+fn renewing_voting_power(amount: u64, lock_duration: i64) -> u64 {
+    (amount as u128)
+        .checked_mul(lock_duration.clamp(0, MAX_VOTE_LOCK_SECONDS) as u128)
+        .unwrap()
+        .checked_div(MAX_VOTE_LOCK_SECONDS as u128)
+        .unwrap() as u64
+}
+
+// Сила голоса линейно убывает до нуля к моменту разблокировки
+pub fn voting_power(staker: &Staker, now: i64) -> u64 {
+    if staker.auto_renew {
+        return renewing_voting_power(staker.amount, staker.lock_duration);
+    }
+    
+    let remaining = vote_unlock_time(staker).saturating_sub(now).max(0);
+    
+    (staker.amount as u128)
+        .checked_mul(remaining as u128)
+        .unwrap()
+        .checked_div(MAX_VOTE_LOCK_SECONDS as u128)
+        .unwrap() as u64
+}
+
+// Сумма voting_power по всем позициям:
+// (Σ amount * unlock - now * Σ amount) / MAX_VOTE_LOCK_SECONDS плюс фиксированная
+// сила автопродляемых позиций.
+// Позиции, истекшие после последнего обновления, вычитаются по недельным корзинам.
+pub fn total_voting_power(staking_pool: &StakingPool, now: i64) -> u64 {
+    let mut locked_amount = staking_pool.ve_locked_amount;
+    let mut unlock_weighted = staking_pool.ve_unlock_weighted;
+    
+    for week in ve_expired_weeks(staking_pool, now) {
+        let unlock_time = week.checked_mul(VOTE_WEEK_SECONDS).unwrap();
+        let expiring = staking_pool.ve_expiries[ve_expiry_index(unlock_time)];
+        locked_amount = locked_amount.checked_sub(expiring).unwrap();
+        unlock_weighted = unlock_weighted
+            .checked_sub((expiring as u128).checked_mul(unlock_time as u128).unwrap())
+            .unwrap();
+    }
+    
+    let elapsed_weight = (locked_amount as u128)
+        .checked_mul(now.max(0) as u128)
+        .unwrap();
+    
+    let decaying_power = unlock_weighted
+        .saturating_sub(elapsed_weight)
+        .checked_div(MAX_VOTE_LOCK_SECONDS as u128)
+        .unwrap() as u64;
+    
+    decaying_power.checked_add(staking_pool.ve_renewing_power).unwrap()
+}
+
+// Переносит истекшие корзины в агрегат
+fn apply_ve_expiries(staking_pool: &mut StakingPool, now: i64) {
+    for week in ve_expired_weeks(staking_pool, now) {
+        let unlock_time = week.checked_mul(VOTE_WEEK_SECONDS).unwrap();
+        let index = ve_expiry_index(unlock_time);
+        let expiring = staking_pool.ve_expiries[index];
+        
+        staking_pool.ve_locked_amount = staking_pool.ve_locked_amount.checked_sub(expiring).unwrap();
+        staking_pool.ve_unlock_weighted = staking_pool.ve_unlock_weighted
+            .checked_sub((expiring as u128).checked_mul(unlock_time as u128).unwrap())
+            .unwrap();
+        staking_pool.ve_expiries[index] = 0;
+    }
+    
+    staking_pool.ve_last_update = staking_pool.ve_last_update.max(now);
+}
+
+// Пересчитывает вклад позиции в общую силу голосов
+fn checkpoint_voting_power(staking_pool: &mut StakingPool, staker: &mut Staker, now: i64) {
+    apply_ve_expiries(staking_pool, now);
+    
+    // Истекший вклад уже снят по расписанию
+    if staker.ve_amount > 0 && staker.ve_unlock_at > now {
+        let index = ve_expiry_index(staker.ve_unlock_at);
+        staking_pool.ve_expiries[index] = staking_pool.ve_expiries[index].checked_sub(staker.ve_amount).unwrap();
+        staking_pool.ve_locked_amount = staking_pool.ve_locked_amount.checked_sub(staker.ve_amount).unwrap();
+        staking_pool.ve_unlock_weighted = staking_pool.ve_unlock_weighted
+            .checked_sub((staker.ve_amount as u128).checked_mul(staker.ve_unlock_at as u128).unwrap())
+            .unwrap();
+    }
+    staking_pool.ve_renewing_power = staking_pool.ve_renewing_power.checked_sub(staker.ve_renewing_power).unwrap();
+    
+    let unlock_time = vote_unlock_time(staker);
+    if staker.auto_renew {
+        staker.ve_amount = 0;
+        staker.ve_unlock_at = 0;
+        staker.ve_renewing_power = renewing_voting_power(staker.amount, staker.lock_duration);
+    } else if staker.amount > 0 && unlock_time > now {
+        staker.ve_amount = staker.amount;
+        staker.ve_unlock_at = unlock_time;
+        staker.ve_renewing_power = 0;
+    } else {
+        staker.ve_amount = 0;
+        staker.ve_unlock_at = 0;
+        staker.ve_renewing_power = 0;
+    }
+    
+    if staker.ve_amount > 0 {
+        let index = ve_expiry_index(staker.ve_unlock_at);
+        staking_pool.ve_expiries[index] = staking_pool.ve_expiries[index].checked_add(staker.ve_amount).unwrap();
+        staking_pool.ve_locked_amount = staking_pool.ve_locked_amount.checked_add(staker.ve_amount).unwrap();
+        staking_pool.ve_unlock_weighted = staking_pool.ve_unlock_weighted
+            .checked_add((staker.ve_amount as u128).checked_mul(staker.ve_unlock_at as u128).unwrap())
+            .unwrap();
+    }
+    staking_pool.ve_renewing_power = staking_pool.ve_renewing_power.checked_add(staker.ve_renewing_power).unwrap();
+}
+
+fn get_tier_multiplier(total_staked: u64) -> u64 {
+    if total_staked >= 50_000_000_000 { // Gold tier
+        200 // 2x multiplier
+    } else if total_staked >= 5_000_000_000 { // Silver tier
+        150 // 1.5x multiplier
+    } else if total_staked >= 500_000_000 { // Bronze tier
+        120 // 1.2x multiplier
+    } else {
+        100 // 1x multiplier
+    }
+}
+
+fn get_time_multiplier(staking_pool: &StakingPool, lock_duration: i64) -> Result<u64> {
+    staking_pool.lock_options
+        .iter()
+        .find(|option| option.duration == lock_duration)
+        .map(|option| option.multiplier)
+        .ok_or_else(|| error!(ErrorCode::InvalidLockDuration))
+}
+
+fn get_base_apy(total_staked: u64, staking_pool: &Account<StakingPool>) -> u64 {
+    if total_staked >= staking_pool.gold_threshold {
+        staking_pool.gold_base_apy as u64
+    } else if total_staked >= staking_pool.silver_threshold {
+        staking_pool.silver_base_apy as u64
+    } else if total_staked >= staking_pool.bronze_threshold {
+        staking_pool.bronze_base_apy as u64
+    } else {
+        5 // Default APY
+    }
+}
+
+// Закрывает истекшие эпохи эмиссии и сбрасывает лимит. Границы эпох кратны epoch_duration;
+// если прошло несколько эпох, все они закрываются сразу, а выплаты относятся к первой из них.
+// Итоги пула и таблица лидеров на момент закрытия сохраняются для снимков в roll_epoch.
+fn roll_emission_epoch(staking_pool: &mut StakingPool, leaderboard: &Leaderboard, now: i64) {
+    let epoch_duration = staking_pool.epoch_duration;
+    if epoch_duration <= 0 || now < staking_pool.epoch_start.checked_add(epoch_duration).unwrap() {
+        return;
+    }
+    
+    let closed_epochs = now
+        .checked_sub(staking_pool.epoch_start)
+        .unwrap()
+        .checked_div(epoch_duration)
+        .unwrap();
+    let next_epoch_start = staking_pool.epoch_start
+        .checked_add(closed_epochs.checked_mul(epoch_duration).unwrap())
+        .unwrap();
+    
+    staking_pool.last_epoch_first = staking_pool.current_epoch;
+    staking_pool.last_epoch_start = staking_pool.epoch_start;
+    staking_pool.last_epoch_end = next_epoch_start;
+    staking_pool.last_epoch_emitted = staking_pool.epoch_emitted;
+    staking_pool.last_epoch_total_staked = staking_pool.total_staked;
+    staking_pool.last_epoch_staker_count = staking_pool.staker_count;
+    staking_pool.last_epoch_leaderboard = leaderboard.entries.clone();
+    staking_pool.current_epoch = staking_pool.current_epoch.checked_add(closed_epochs as u64).unwrap();
+    staking_pool.epoch_start = next_epoch_start;
+    staking_pool.epoch_emitted = 0;
+    
+    emit!(EpochClosedEvent {
+        epoch: staking_pool.last_epoch_first,
+        closed_epochs: closed_epochs as u64,
+        start_time: staking_pool.last_epoch_start,
+        end_time: staking_pool.last_epoch_end,
+        total_staked: staking_pool.last_epoch_total_staked,
+        rewards_paid: staking_pool.last_epoch_emitted,
+        staker_count: staking_pool.last_epoch_staker_count,
+        leaderboard: staking_pool.last_epoch_leaderboard.clone(),
+    });
+}
+
+// Выплата ограничена балансом хранилища и лимитом эпохи (эпоха закрывается в roll_emission_epoch)
+fn emission_payout(staking_pool: &StakingPool, staker: &Staker, vault_balance: u64) -> u64 {
+    let epoch_remaining = staking_pool.epoch_emission_cap.saturating_sub(staking_pool.epoch_emitted);
+    
+    staker.accrued_rewards.min(vault_balance).min(epoch_remaining)
+}
+
+// Учет выплаченной эмиссии и доля реферера.
+// При нехватке бюджета APY позиции снижается пропорционально выплате: начисленные rewards
+// закрываются по сниженному APY, и он действует до следующего пересчета APY позиции.
+fn record_emission<'info>(
+    staking_pool: &mut StakingPool,
+    staker: &mut Account<'info, Staker>,
+    referrer_account: &mut Option<Account<'info, Referrer>>,
+    payout: u64,
+    vault_balance: u64,
+    now: i64,
+) -> Result<()> {
+    let rewards = staker.accrued_rewards;
+    
+    if payout < rewards {
+        let reduced_apy = (staker.apy as u128)
+            .checked_mul(payout as u128)
+            .unwrap()
+            .checked_div(rewards as u128)
+            .unwrap() as u64;
+        
+        emit!(RewardsBudgetLowEvent {
+            staker: staker.key(),
+            requested_rewards: rewards,
+            paid_rewards: payout,
+            apy: staker.apy,
+            reduced_apy,
+            remaining_budget: vault_balance.checked_sub(payout).unwrap(),
+            timestamp: now,
+        });
+        
+        staker.apy = reduced_apy;
+    }
+    
+    staker.accrued_rewards = 0;
+    staking_pool.epoch_emitted = staking_pool.epoch_emitted.checked_add(payout).unwrap();
+    staking_pool.total_rewards_distributed = staking_pool.total_rewards_distributed.checked_add(payout).unwrap();
+    
+    // Реферер получает долю выплаченных rewards в пределах бюджета
+    if let Some(referrer) = staker.referrer {
+        let referrer_account = referrer_account.as_mut().ok_or(ErrorCode::ReferrerNotRegistered)?;
+        require!(referrer_account.referrer == referrer, ErrorCode::ReferrerNotRegistered);
+        
+        let referral_reward = payout
+            .checked_mul(staking_pool.referral_bps as u64)
+            .unwrap()
+            .checked_div(10_000)
+            .unwrap()
+            .min(staking_pool.referral_budget);
+        
+        if referral_reward > 0 {
+            staking_pool.referral_budget = staking_pool.referral_budget.checked_sub(referral_reward).unwrap();
+            referrer_account.accrued_rewards = referrer_account.accrued_rewards.checked_add(referral_reward).unwrap();
+            
+            emit!(ReferralRewardAccruedEvent {
+                referrer,
+                referee: staker.key(),
+                amount: referral_reward,
+                remaining_budget: staking_pool.referral_budget,
+                timestamp: now,
+            });
+        }
+    }
+    
+    Ok(())
+}
+
+// Держатель позиции или его делегат с достаточными правами.
+// Делегирование теряет силу после передачи NFT позиции.
+pub fn can_act(staker: &Staker, holder: Pubkey, actor: Pubkey, required: DelegateScope) -> bool {
+    actor == holder
+        || (staker.delegate == Some(actor)
+            && staker.delegate_granter == holder
+            && staker.delegate_scope >= required)
+}
+
+// Поддерживает топ позиций по сумме стейка, отсортированный по убыванию.
+// Участник полной таблицы, опустившийся ниже всех остальных, выбывает:
+// позиции вне таблицы могут быть больше его новой суммы.
+fn update_leaderboard(leaderboard: &mut Leaderboard, staker: Pubkey, amount: u64) {
+    let was_full = leaderboard.entries.len() >= LEADERBOARD_SIZE;
+    let previous = leaderboard.entries.iter().find(|entry| entry.staker == staker).map(|entry| entry.amount);
+    leaderboard.entries.retain(|entry| entry.staker != staker);
+    
+    if amount > 0 {
+        let position = leaderboard.entries
+            .iter()
+            .position(|entry| entry.amount < amount)
+            .unwrap_or(leaderboard.entries.len());
+        
+        let dropped_below_board = was_full
+            && previous.map_or(false, |previous| amount < previous)
+            && position == leaderboard.entries.len();
+        
+        if position < LEADERBOARD_SIZE && !dropped_below_board {
+            leaderboard.entries.insert(position, LeaderboardEntry { staker, amount });
+            leaderboard.entries.truncate(LEADERBOARD_SIZE);
+        }
+    }
+}
+
+// Число позиций с ненулевым стейком
+fn update_staker_count(staking_pool: &mut StakingPool, old_amount: u64, new_amount: u64) {
+    if old_amount == 0 && new_amount > 0 {
+        staking_pool.staker_count = staking_pool.staker_count.checked_add(1).unwrap();
+    } else if old_amount > 0 && new_amount == 0 {
+        staking_pool.staker_count = staking_pool.staker_count.checked_sub(1).unwrap();
+    }
+}
+
+fn calculate_staking_level(total_staked: u64, staking_pool: &Account<StakingPool>) -> StakingTier {
+    if total_staked >= staking_pool.gold_threshold {
+        StakingTier::Gold
+    } else if total_staked >= staking_pool.silver_threshold {
+        StakingTier::Silver
+    } else if total_staked >= staking_pool.bronze_threshold {
+        StakingTier::Bronze
+    } else {
+        StakingTier::Bronze
+    }
+}
+
+// Accounts
+#[account]
+pub struct StakingPool {
+    pub authority: Pubkey,
+    pub total_staked: u64,
+    pub total_rewards_distributed: u64,
+    
+    // Tier thresholds
+    pub bronze_threshold: u64,
+    pub silver_threshold: u64,
+    pub gold_threshold: u64,
+    
+    // Base APY for each tier
+    pub bronze_base_apy: u8,
+    pub silver_base_apy: u8,
+    pub gold_base_apy: u8,
+    
+    // Rewards budget
+    pub rewards_vault: Pubkey,
+    pub epoch_duration: i64,
+    pub epoch_emission_cap: u64,
+    pub epoch_start: i64,
+    pub epoch_emitted: u64,
+    pub last_epoch_start: i64,
+    pub last_epoch_end: i64,
+    pub last_epoch_emitted: u64,
+    pub last_epoch_first: u64, // First epoch closed by the last rollover
+    pub last_epoch_total_staked: u64,
+    pub last_epoch_staker_count: u64,
+    pub last_epoch_leaderboard: Vec<LeaderboardEntry>,
+    
+    // Unbonding
+    pub stake_vault: Pubkey,
+    pub unbonding_cooldown: i64,
+    pub total_unbonding: u64,
+    
+    // Revenue share
+    pub revenue_mint: Pubkey,
+    pub revenue_vault: Pubkey,
+    pub revenue_per_share: u128,
+    pub total_revenue_deposited: u64,
+    pub total_revenue_distributed: u64,
+    
+    // Vote-escrow aggregate
+    pub ve_locked_amount: u64,
+    pub ve_unlock_weighted: u128,
+    pub ve_renewing_power: u64,
+    pub ve_last_update: i64,
+    pub ve_expiries: [u64; VE_EXPIRY_WEEKS], // locked amount by unlock week
+    
+    // Slashing
+    pub slasher: Pubkey,
+    pub treasury: Pubkey,
+    pub min_reporter_stake: u64,
+    
+    // Referrals
+    pub referral_bps: u16,
+    pub referral_budget: u64,
+    
+    // Emergency
+    pub emergency_mode: bool,
+    
+    // Allowed lock durations (seconds) with their multipliers
+    pub lock_options: Vec<LockOption>,
+    
+    // Pool-wide stats
+    pub staker_count: u64,
+    pub current_epoch: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct LockOption {
+    pub duration: i64,
+    pub multiplier: u64,
+}
+
+#[account]
+pub struct Staker {
+    pub staker: Pubkey,
+    pub position_mint: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+    pub apy: u64,
+    pub level: StakingTier,
+    pub lock_duration: i64,
+    pub unlock_at: i64,
+    pub stake_time: i64,
+    pub last_claim_time: i64,
+    pub accrued_rewards: u64,
+    pub next_ticket_id: u64,
+    pub unbonding_amount: u64,
+    pub revenue_debt: u128,
+    pub revenue_owed: u64,
+    pub ve_amount: u64,
+    pub ve_unlock_at: i64,
+    pub is_reporter: bool,
+    pub referrer: Option<Pubkey>,
+    pub auto_renew: bool,
+    pub ve_renewing_power: u64,
+    pub delegate: Option<Pubkey>,
+    pub delegate_scope: DelegateScope,
+    pub delegate_granter: Pubkey,
+    pub time_multiplier: u64, // Lock multiplier applied to apy at stake or migration
+}
+
+#[account]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+    pub staker: Pubkey,
+    pub amount: u64,
+}
+
+#[account]
+pub struct EpochSnapshot {
+    pub epoch: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub total_staked: u64,
+    pub rewards_paid: u64,
+    pub staker_count: u64,
+    pub leaderboard: Vec<LeaderboardEntry>,
+}
+
+#[account]
+pub struct MigrationRecord {
+    pub ndt_staking_account: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub migrated_at: i64,
+}
+
+// Раскладка ndt::StakingAccount для чтения без зависимости от программы ndt
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct NdtStakingAccount {
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub lock_period: u64,
+    pub stake_time: i64,
+    pub last_claim_time: i64,
+    pub apr: u64,
+    pub total_staked: u64,
+}
+
+#[account]
+pub struct Referrer {
+    pub referrer: Pubkey,
+    pub accrued_rewards: u64,
+    pub total_claimed: u64,
+    pub referee_count: u64,
+}
+
+// Реферер пользователя, общий для всех его позиций
+#[account]
+pub struct Referral {
+    pub owner: Pubkey,
+    pub referrer: Option<Pubkey>, // Set by the first stake, never changed
+}
+
+#[account]
+pub struct Reporter {
+    pub staker: Pubkey,
+    pub bonded_at: i64,
+    pub total_slashed: u64,
+    pub slash_count: u32,
+    pub exit_requested_at: i64,
+}
+
+#[account]
+pub struct UnbondingTicket {
+    pub staker: Pubkey,
+    pub owner: Pubkey,
+    pub ticket_id: u64,
+    pub amount: u64,
+    pub requested_at: i64,
+    pub unlock_time: i64,
+}
+
+// Права делегата, по возрастанию: каждый уровень включает предыдущий
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum DelegateScope {
+    #[default]
+    None,
+    ClaimOnly,
+    ClaimAndRestake,
+    Full,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum StakingTier {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct StakingInfo {
+    pub total_staked: u64,
+    pub apy: u64,
+    pub level: StakingTier,
+    pub lock_duration: i64,
+    pub unlock_at: i64,
+    pub auto_renew: bool,
+    pub pending_rewards: u64,
+    pub lock_period_remaining: i64,
+    pub pending_revenue: u64,
+    pub unbonding_amount: u64,
+    pub projected_rewards_at_unlock: u64,
+    pub next_level_threshold: Option<u64>,
+    
+    // Multiplier breakdown (percent, 100 = 1x)
+    pub base_apy: u64,
+    pub tier_multiplier: u64,
+    pub time_multiplier: u64,
+    
+    // Pool totals
+    pub pool_total_staked: u64,
+    pub pool_total_rewards_distributed: u64,
+    pub pool_staker_count: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct StakerPerks {
+    pub tier: StakingTier,
+    pub perks: u32,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct VotingPowerInfo {
+    pub voting_power: u64,
+    pub total_voting_power: u64,
+    pub unlock_time: i64,
+    pub timestamp: i64,
+}
+
+// Events
+#[event]
+pub struct StakeEvent {
+    pub staker: Pubkey,
+    pub position_mint: Pubkey,
+    pub amount: u64,
+    pub lock_duration: i64,
+    pub unlock_at: i64,
+    pub apy: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnstakeRequestedEvent {
+    pub staker: Pubkey,
+    pub actor: Pubkey,
+    pub ticket: Pubkey,
+    pub amount: u64,
+    pub unlock_time: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnstakeEvent {
+    pub staker: Pubkey,
+    pub actor: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnbondCancelledEvent {
+    pub staker: Pubkey,
+    pub actor: Pubkey,
+    pub ticket: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnbondingCooldownUpdatedEvent {
+    pub unbonding_cooldown: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimRewardsEvent {
+    pub staker: Pubkey,
+    pub actor: Pubkey,
+    pub rewards: u64,
+    pub revenue_rewards: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CompoundRewardsEvent {
+    pub staker: Pubkey,
+    pub actor: Pubkey,
+    pub amount: u64,
+    pub new_stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DelegateUpdatedEvent {
+    pub staker: Pubkey,
+    pub holder: Pubkey,
+    pub delegate: Option<Pubkey>,
+    pub scope: DelegateScope,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevenueDepositedEvent {
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub revenue_per_share: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsFundedEvent {
+    pub amount: u64,
+    pub vault_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmissionCapUpdatedEvent {
+    pub epoch_duration: i64,
+    pub epoch_emission_cap: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsBudgetLowEvent {
+    pub staker: Pubkey,
+    pub requested_rewards: u64,
+    pub paid_rewards: u64,
+    pub apy: u64,
+    pub reduced_apy: u64,
+    pub remaining_budget: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SlashingConfiguredEvent {
+    pub slasher: Pubkey,
+    pub treasury: Pubkey,
+    pub min_reporter_stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReporterRegisteredEvent {
+    pub staker: Pubkey,
+    pub reporter: Pubkey,
+    pub bonded_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReporterExitRequestedEvent {
+    pub staker: Pubkey,
+    pub reporter: Pubkey,
+    pub exit_available_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReporterDeregisteredEvent {
+    pub staker: Pubkey,
+    pub reporter: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SlashEvent {
+    pub staker: Pubkey,
+    pub reporter: Pubkey,
+    pub slasher: Pubkey,
+    pub amount: u64,
+    pub remaining_stake: u64,
+    pub reason: String,
+    pub dispute: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralsConfiguredEvent {
+    pub referral_bps: u16,
+    pub referral_budget: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRewardAccruedEvent {
+    pub referrer: Pubkey,
+    pub referee: Pubkey,
+    pub amount: u64,
+    pub remaining_budget: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRewardsClaimedEvent {
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyModeToggledEvent {
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyWithdrawEvent {
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub forfeited_rewards: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LockOptionsUpdatedEvent {
+    pub lock_options: Vec<LockOption>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MigratedFromNdtEvent {
+    pub staker: Pubkey,
+    pub position_mint: Pubkey,
+    pub ndt_staking_account: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,
+    pub settled_rewards: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AutoRenewUpdatedEvent {
+    pub staker: Pubkey,
+    pub actor: Pubkey,
+    pub enabled: bool,
+    pub unlock_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EpochClosedEvent {
+    pub epoch: u64,
+    pub closed_epochs: u64, // Epochs closed at once; only the first had emissions
+    pub start_time: i64,
+    pub end_time: i64,
+    pub total_staked: u64,
+    pub rewards_paid: u64,
+    pub staker_count: u64,
+    pub leaderboard: Vec<LeaderboardEntry>,
+}
+
+#[event]
+pub struct StakingLevelUpdatedEvent {
+    pub staker: Pubkey,
+    pub old_level: StakingTier,
+    pub new_level: StakingTier,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TierApyUpdatedEvent {
+    pub tier: StakingTier,
+    pub new_apy: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TierThresholdsUpdatedEvent {
+    pub bronze_threshold: u64,
+    pub silver_threshold: u64,
+    pub gold_threshold: u64,
+    pub timestamp: i64,
+}
+
+// Contexts
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 8
+            + 8 + 8 + 8 + 1 + 1 + 1
+            + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 4 + LEADERBOARD_SIZE * (32 + 8)
+            + 32 + 8 + 8
+            + 32 + 32 + 16 + 8 + 8
+            + 8 + 16 + 8 + 8 + VE_EXPIRY_WEEKS * 8
+            + 32 + 32 + 8
+            + 2 + 8
+            + 1
+            + 4 + MAX_LOCK_OPTIONS * (8 + 8)
+            + 8 + 8,
+        seeds = [b"staking"],
+        bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetLockOptions<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + 8 + 1 + 33 + 1 + 8 + 33 + 1 + 32 + 8,
+        seeds = [b"staker", position_mint.key().as_ref()],
+        bump
+    )]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = staking_pool
+    )]
+    pub position_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = position_mint,
+        associated_token::authority = authority
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(mut, constraint = staker_token_account.owner == authority.key())]
+    pub staker_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stake_vault"], bump, address = staking_pool.stake_vault)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"referrer", referrer_account.referrer.as_ref()], bump)]
+    pub referrer_account: Option<Account<'info, Referrer>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 33,
+        seeds = [b"referral", authority.key().as_ref()],
+        bump
+    )]
+    pub referral: Account<'info, Referral>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateFromNdt<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + 8 + 1 + 33 + 1 + 8 + 33 + 1 + 32 + 8,
+        seeds = [b"staker", position_mint.key().as_ref()],
+        bump
+    )]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = staking_pool
+    )]
+    pub position_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = position_mint,
+        associated_token::authority = authority
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(constraint = ndt_staker_token_account.owner == authority.key() @ ErrorCode::Unauthorized)]
+    pub ndt_staker_token_account: Account<'info, TokenAccount>,
+    /// CHECK: владелец и адрес PDA проверяются ограничениями, тип - в read_ndt_staking_account
+    #[account(
+        mut,
+        owner = ndt_program::ID,
+        seeds = [b"staking", ndt_staker_token_account.key().as_ref()],
+        bump,
+        seeds::program = ndt_program::ID
+    )]
+    pub ndt_staking_account: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 8,
+        seeds = [b"ndt_migration", ndt_staking_account.key().as_ref()],
+        bump
+    )]
+    pub migration_record: Account<'info, MigrationRecord>,
+    /// CHECK: PDA состояния ndt, подписывает перевод из хранилища ndt
+    #[account(seeds = [b"ndt"], bump, seeds::program = ndt_program::ID)]
+    pub ndt_state: UncheckedAccount<'info>,
+    /// CHECK: хранилище застейканных токенов ndt, проверяется программой ndt
+    #[account(mut, seeds = [b"stake_custody"], bump, seeds::program = ndt_program::ID)]
+    pub ndt_stake_custody: UncheckedAccount<'info>,
+    /// CHECK: PDA без данных, подписывает CPI в программу ndt
+    #[account(seeds = [b"ndt_migrator"], bump)]
+    pub ndt_migrator: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"stake_vault"], bump, address = staking_pool.stake_vault)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"rewards_vault"], bump, address = staking_pool.rewards_vault)]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = rewards_account.mint == rewards_vault.mint,
+        constraint = rewards_account.owner == authority.key() @ ErrorCode::InvalidPayoutAccount
+    )]
+    pub rewards_account: Account<'info, TokenAccount>,
+    /// CHECK: адрес программы ndt
+    #[account(address = ndt_program::ID)]
+    pub ndt_program: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RequestUnstake<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = can_act(&staker, position_token_account.owner, authority.key(), DelegateScope::Full) @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8,
+        seeds = [b"unbonding", staker.key().as_ref(), &staker.next_ticket_id.to_le_bytes()],
+        bump
+    )]
+    pub ticket: Account<'info, UnbondingTicket>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawUnbonded<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, address = ticket.staker)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"unbonding", staker.key().as_ref(), &ticket.ticket_id.to_le_bytes()],
+        bump,
+        constraint = can_act(&staker, ticket.owner, authority.key(), DelegateScope::Full) @ ErrorCode::Unauthorized
+    )]
+    pub ticket: Account<'info, UnbondingTicket>,
+    /// CHECK: владелец позиции, получает ренту тикета
+    #[account(mut, address = ticket.owner)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"stake_vault"], bump, address = staking_pool.stake_vault)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = staker_token_account.owner == ticket.owner @ ErrorCode::InvalidPayoutAccount)]
+    pub staker_token_account: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelUnbond<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = can_act(&staker, position_token_account.owner, authority.key(), DelegateScope::Full) @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"unbonding", staker.key().as_ref(), &ticket.ticket_id.to_le_bytes()],
+        bump,
+        constraint = ticket.owner == position_token_account.owner @ ErrorCode::Unauthorized
+    )]
+    pub ticket: Account<'info, UnbondingTicket>,
+    /// CHECK: владелец позиции, получает ренту тикета
+    #[account(mut, address = ticket.owner)]
+    pub owner: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = can_act(&staker, position_token_account.owner, authority.key(), DelegateScope::ClaimOnly) @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    // Выплаты идут только держателю позиции, даже если вызывает делегат
+    #[account(
+        mut,
+        constraint = rewards_account.mint == rewards_vault.mint,
+        constraint = rewards_account.owner == position_token_account.owner @ ErrorCode::InvalidPayoutAccount
+    )]
+    pub rewards_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"rewards_vault"], bump, address = staking_pool.rewards_vault)]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = revenue_account.mint == revenue_vault.mint,
+        constraint = revenue_account.owner == position_token_account.owner @ ErrorCode::InvalidPayoutAccount
+    )]
+    pub revenue_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"revenue_vault"], bump, address = staking_pool.revenue_vault)]
+    pub revenue_vault: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"referrer", referrer_account.referrer.as_ref()], bump)]
+    pub referrer_account: Option<Account<'info, Referrer>>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundRewards<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = can_act(&staker, position_token_account.owner, authority.key(), DelegateScope::ClaimAndRestake) @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"rewards_vault"], bump, address = staking_pool.rewards_vault)]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"stake_vault"],
+        bump,
+        address = staking_pool.stake_vault,
+        constraint = stake_vault.mint == rewards_vault.mint @ ErrorCode::CompoundMintMismatch
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"referrer", referrer_account.referrer.as_ref()], bump)]
+    pub referrer_account: Option<Account<'info, Referrer>>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
-fn calculate_staking_level(total_staked: u64, staking_pool: &Account<StakingPool>) -> StakingTier {
-    if total_staked >= staking_pool.gold_threshold {
-        StakingTier::Gold
-    } else if total_staked >= staking_pool.silver_threshold {
-        StakingTier::Silver
-    } else if total_staked >= staking_pool.bronze_threshold {
-        StakingTier::Bronze
-    } else {
-        StakingTier::Bronze
-    }
+#[derive(Accounts)]
+pub struct SetDelegate<'info> {
+    #[account(seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.owner == authority.key() @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
 }
 
-// Accounts
-#[account]
-pub struct StakingPool {
-    pub authority: Pubkey,
-    pub total_staked: u64,
-    pub total_rewards_distributed: u64,
-    
-    // Tier thresholds
-    pub bronze_threshold: u64,
-    pub silver_threshold: u64,
-    pub gold_threshold: u64,
-    
-    // Base APY for each tier
-    pub bronze_base_apy: u8,
-    pub silver_base_apy: u8,
-    pub gold_base_apy: u8,
+#[derive(Accounts)]
+pub struct SetEmergencyMode<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    pub authority: Signer<'info>,
 }
 
-#[account]
-pub struct Staker {
-    pub staker: Pubkey,
-    pub amount: u64,
-    pub total_staked: u64,
-    pub apy: u64,
-    pub level: StakingTier,
-    pub lock_period_months: u8,
-    pub stake_time: i64,
-    pub last_claim_time: i64,
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.owner == authority.key() @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stake_vault"], bump, address = staking_pool.stake_vault)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = staker_token_account.owner == authority.key())]
+    pub staker_token_account: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum StakingTier {
-    Bronze,
-    Silver,
-    Gold,
+#[derive(Accounts)]
+pub struct ConfigureReferrals<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    pub authority: Signer<'info>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct StakingInfo {
-    pub total_staked: u64,
-    pub apy: u64,
-    pub level: StakingTier,
-    pub lock_period_months: u8,
-    pub pending_rewards: u64,
-    pub lock_period_remaining: i64,
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + 32 + 8 + 8 + 8,
+        seeds = [b"referrer", referrer.key().as_ref()],
+        bump
+    )]
+    pub referrer_account: Account<'info, Referrer>,
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-// Events
-#[event]
-pub struct StakeEvent {
-    pub staker: Pubkey,
-    pub amount: u64,
-    pub lock_period_months: u8,
-    pub apy: u64,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut, seeds = [b"referrer", referrer.key().as_ref()], bump)]
+    pub referrer_account: Account<'info, Referrer>,
+    #[account(mut, seeds = [b"rewards_vault"], bump, address = staking_pool.rewards_vault)]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = rewards_account.mint == rewards_vault.mint)]
+    pub rewards_account: Account<'info, TokenAccount>,
+    pub referrer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
-#[event]
-pub struct UnstakeEvent {
-    pub staker: Pubkey,
-    pub amount: u64,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct InitializeRevenueVault<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    pub revenue_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"revenue_vault"],
+        bump,
+        token::mint = revenue_mint,
+        token::authority = staking_pool
+    )]
+    pub revenue_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
-#[event]
-pub struct ClaimRewardsEvent {
-    pub staker: Pubkey,
-    pub rewards: u64,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct DepositRevenue<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"revenue_vault"], bump, address = staking_pool.revenue_vault)]
+    pub revenue_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub source: Account<'info, TokenAccount>,
+    pub depositor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
-#[event]
-pub struct StakingLevelUpdatedEvent {
-    pub staker: Pubkey,
-    pub old_level: StakingTier,
-    pub new_level: StakingTier,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct InitializeRewardsVault<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    pub reward_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"rewards_vault"],
+        bump,
+        token::mint = reward_mint,
+        token::authority = staking_pool
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
-#[event]
-pub struct TierApyUpdatedEvent {
-    pub tier: StakingTier,
-    pub new_apy: u8,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"rewards_vault"], bump, address = staking_pool.rewards_vault)]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub funder: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
-#[event]
-pub struct TierThresholdsUpdatedEvent {
-    pub bronze_threshold: u64,
-    pub silver_threshold: u64,
-    pub gold_threshold: u64,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct InitializeLeaderboard<'info> {
+    #[account(seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 4 + LEADERBOARD_SIZE * (32 + 8),
+        seeds = [b"leaderboard"],
+        bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-// Contexts
 #[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(init, payer = authority, seeds = [b"staking"], bump)]
+#[instruction(epoch: u64)]
+pub struct RollEpoch<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
     pub staking_pool: Account<'info, StakingPool>,
+    #[account(seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 8 + 8 + 8 + 8 + 8 + 8 + 4 + LEADERBOARD_SIZE * (32 + 8),
+        seeds = [b"epoch".as_ref(), &epoch.to_le_bytes()],
+        bump
+    )]
+    pub epoch_snapshot: Account<'info, EpochSnapshot>,
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct Stake<'info> {
+pub struct InitializeStakeVault<'info> {
     #[account(mut, seeds = [b"staking"], bump)]
     pub staking_pool: Account<'info, StakingPool>,
+    pub stake_mint: Account<'info, Mint>,
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 8 + 8 + 8 + 1 + 1 + 8 + 8,
-        seeds = [b"staker", authority.key().as_ref()],
-        bump
+        seeds = [b"stake_vault"],
+        bump,
+        token::mint = stake_mint,
+        token::authority = staking_pool
     )]
-    pub staker: Account<'info, Staker>,
+    pub stake_vault: Account<'info, TokenAccount>,
     #[account(mut)]
-    pub rewards_account: Account<'info, TokenAccount>,
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct Unstake<'info> {
+pub struct UpdateUnbondingCooldown<'info> {
     #[account(mut, seeds = [b"staking"], bump)]
     pub staking_pool: Account<'info, StakingPool>,
-    #[account(mut, seeds = [b"staker", authority.key().as_ref()], bump)]
-    pub staker: Account<'info, Staker>,
-    #[account(mut)]
-    pub rewards_account: Account<'info, TokenAccount>,
     pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimRewards<'info> {
+pub struct UpdateEmissionCap<'info> {
     #[account(mut, seeds = [b"staking"], bump)]
     pub staking_pool: Account<'info, StakingPool>,
-    #[account(mut, seeds = [b"staker", authority.key().as_ref()], bump)]
-    pub staker: Account<'info, Staker>,
-    #[account(mut)]
-    pub rewards_account: Account<'info, TokenAccount>,
     pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
 pub struct UpdateStakingLevel<'info> {
     #[account(mut, seeds = [b"staking"], bump)]
     pub staking_pool: Account<'info, StakingPool>,
-    #[account(mut, seeds = [b"staker", authority.key().as_ref()], bump)]
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
     pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.owner == authority.key() @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
     pub authority: Signer<'info>,
 }
 
@@ -482,12 +3007,146 @@ pub struct UpdateTierThresholds<'info> {
 }
 
 #[derive(Accounts)]
-pub struct GetStakingInfo<'info> {
-    #[account(seeds = [b"staker", authority.key().as_ref()], bump)]
+pub struct ConfigureSlashing<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(constraint = treasury.mint == stake_vault.mint)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(seeds = [b"stake_vault"], bump, address = staking_pool.stake_vault)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterReporter<'info> {
+    #[account(seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.owner == authority.key() @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 8 + 4 + 8,
+        seeds = [b"reporter", staker.key().as_ref()],
+        bump
+    )]
+    pub reporter: Account<'info, Reporter>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestReporterExit<'info> {
+    #[account(seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.owner == authority.key() @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reporter", staker.key().as_ref()], bump)]
+    pub reporter: Account<'info, Reporter>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterReporter<'info> {
+    #[account(seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.owner == authority.key() @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(mut, close = authority, seeds = [b"reporter", staker.key().as_ref()], bump)]
+    pub reporter: Account<'info, Reporter>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Slash<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(mut, seeds = [b"reporter", staker.key().as_ref()], bump)]
+    pub reporter: Account<'info, Reporter>,
+    #[account(mut, seeds = [b"stake_vault"], bump, address = staking_pool.stake_vault)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut, address = staking_pool.treasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    pub slasher: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CheckpointVoting<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoRenew<'info> {
+    #[account(mut, seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut, seeds = [b"staker", staker.position_mint.as_ref()], bump)]
     pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = can_act(&staker, position_token_account.owner, authority.key(), DelegateScope::Full) @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct GetVotingPower<'info> {
+    #[account(seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+}
+
+#[derive(Accounts)]
+pub struct GetStakerPerks<'info> {
+    #[account(seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+    #[account(
+        constraint = position_token_account.mint == staker.position_mint @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct GetStakingInfo<'info> {
+    #[account(seeds = [b"staking"], bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(seeds = [b"staker", staker.position_mint.as_ref()], bump)]
+    pub staker: Account<'info, Staker>,
+}
+
 // Error codes
 #[error_code]
 pub enum ErrorCode {
@@ -499,4 +3158,66 @@ pub enum ErrorCode {
     LockPeriodNotExpired,
     #[msg("No rewards to claim")]
     NoRewardsToClaim,
+    #[msg("Rewards budget exhausted for this epoch")]
+    RewardsBudgetExhausted,
+    #[msg("Epoch duration must be positive")]
+    InvalidEpochDuration,
+    #[msg("Unbonding cooldown has not finished")]
+    UnbondingNotFinished,
+    #[msg("Unbonding cooldown cannot be negative")]
+    InvalidUnbondingCooldown,
+    #[msg("Signer does not hold the stake position NFT")]
+    NotPositionHolder,
+    #[msg("Revenue amount must be positive")]
+    InvalidRevenueAmount,
+    #[msg("Nothing is staked to receive revenue")]
+    NoStakeForRevenue,
+    #[msg("Reporter stake would fall below the required minimum")]
+    ReporterStakeTooLow,
+    #[msg("Slash reason is too long")]
+    SlashReasonTooLong,
+    #[msg("Cannot refer yourself")]
+    SelfReferral,
+    #[msg("Referrer account is missing or not registered")]
+    ReferrerNotRegistered,
+    #[msg("Referral share too high (max 20%)")]
+    ReferralBpsTooHigh,
+    #[msg("Pool is in emergency mode: only emergency_withdraw is allowed")]
+    EmergencyModeActive,
+    #[msg("Pool is not in emergency mode")]
+    EmergencyModeInactive,
+    #[msg("Lock duration is not one of the allowed options")]
+    InvalidLockDuration,
+    #[msg("Invalid lock options")]
+    InvalidLockOptions,
+    #[msg("Account is not an ndt staking account")]
+    InvalidNdtStakingAccount,
+    #[msg("ndt staking account has nothing to migrate")]
+    NothingToMigrate,
+    #[msg("Account is not a canonical staking account")]
+    InvalidStakerAccount,
+    #[msg("Current epoch has not finished yet")]
+    EpochNotFinished,
+    #[msg("Auto-renew is already in the requested state")]
+    AutoRenewUnchanged,
+    #[msg("Delegate scope must grant at least claim rights")]
+    InvalidDelegateScope,
+    #[msg("Invalid delegate")]
+    InvalidDelegate,
+    #[msg("Payouts must go to the position holder")]
+    InvalidPayoutAccount,
+    #[msg("Rewards and stake use different mints, compounding is unavailable")]
+    CompoundMintMismatch,
+    #[msg("Reporter exit already requested")]
+    ReporterExitAlreadyRequested,
+    #[msg("Reporter exit has not been requested")]
+    ReporterExitNotRequested,
+    #[msg("Reporter exit delay has not passed")]
+    ReporterExitDelayNotPassed,
+    #[msg("Only epochs closed by the latest rollover can be snapshotted")]
+    EpochSnapshotUnavailable,
+    #[msg("Reward calculation overflow")]
+    RewardsOverflow,
+    #[msg("A different referrer is already recorded for this user")]
+    ReferrerAlreadySet,
 }
\ No newline at end of file
@@ -1,4 +1,15 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as sysvar_instructions, load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::mpl_token_metadata::types::{Creator, DataV2};
+use anchor_spl::metadata::{
+    create_master_edition_v3, create_metadata_accounts_v3, mint_new_edition_from_master_edition_via_token,
+    sign_metadata, update_metadata_accounts_v2, CreateMasterEditionV3, CreateMetadataAccountsV3, Metadata,
+    MintNewEditionFromMasterEditionViaToken, SignMetadata, UpdateMetadataAccountsV2,
+};
 use anchor_spl::token::{Mint, Token, TokenAccount};
 use std::str::FromStr;
 
@@ -16,6 +27,29 @@ pub mod tracknft {
         track_nft.authority = authority.key();
         track_nft.royalty_percentage = 10; // 10% роялти по умолчанию
         track_nft.total_tracks = 0;
+        track_nft.platform_fee_bps = DEFAULT_PLATFORM_FEE_BPS;
+        
+        // Хранилище комиссий задает валюту всех продаж
+        track_nft.fee_vault = ctx.accounts.fee_vault.key();
+        track_nft.payment_mint = ctx.accounts.fee_vault.mint;
+        
+        Ok(())
+    }
+
+    // Регистрация профиля артиста: счетчик задает адреса его треков
+    pub fn register_artist(ctx: Context<RegisterArtist>) -> Result<()> {
+        let artist_profile = &mut ctx.accounts.artist_profile;
+        let authority = &ctx.accounts.authority;
+        
+        artist_profile.artist = authority.key();
+        artist_profile.track_count = 0;
+        artist_profile.created_at = Clock::get()?.unix_timestamp;
+        
+        emit!(ArtistRegisteredEvent {
+            artist: authority.key(),
+            profile: artist_profile.key(),
+            timestamp: artist_profile.created_at,
+        });
         
         Ok(())
     }
@@ -28,13 +62,16 @@ pub mod tracknft {
         ipfs_hash: String,
         price: u64,
         royalty_percentage: u8,
+        max_editions: u64,
     ) -> Result<()> {
         let track_nft = &mut ctx.accounts.track_nft;
         let track = &mut ctx.accounts.track;
-        let artist = &ctx.accounts.artist;
+        let artist_profile = &mut ctx.accounts.artist_profile;
         let authority = &ctx.accounts.authority;
         
         require!(royalty_percentage <= 50, ErrorCode::RoyaltyTooHigh);
+        require!(track_name.len() <= MAX_NAME_LENGTH, ErrorCode::NameTooLong);
+        require!(ipfs_hash.len() + IPFS_URI_PREFIX.len() <= MAX_URI_LENGTH, ErrorCode::UriTooLong);
         
         // Инициализируем NFT
         track.track_name = track_name;
@@ -42,22 +79,65 @@ pub mod tracknft {
         track.ipfs_hash = ipfs_hash;
         track.price = price;
         track.royalty_percentage = royalty_percentage;
+        track.max_editions = max_editions;
+        track.editions_sold = 0;
         track.is_listed = true;
+        track.sync_price = 0;
+        track.commercial_price = 0;
+        track.exclusive_price = 0;
+        track.terms_uri = String::new();
+        track.exclusive_license = None;
         track.play_count = 0;
+        track.sales_count = 0;
         track.like_count = 0;
         track.total_royalties_paid = 0;
-        track.creator = artist.key();
+        track.creator = authority.key();
+        track.track_index = artist_profile.track_count;
+        track.mint = ctx.accounts.mint.key();
         track.mint_time = Clock::get()?.unix_timestamp;
         
-        // Увеличиваем счетчик треков
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        // Выпускаем master NFT; он хранится у программы, которая печатает с него копии
+        issue_nft(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_metadata_program,
+            &ctx.accounts.system_program,
+            &ctx.accounts.rent,
+            &ctx.accounts.mint,
+            &ctx.accounts.master_token_account,
+            &ctx.accounts.metadata,
+            &ctx.accounts.master_edition,
+            track_nft,
+            authority,
+            track_metadata(track, track_nft.key(), false),
+            if max_editions > 0 { Some(max_editions) } else { None },
+            signer,
+        )?;
+        
+        // Подпись артиста верифицирует его в списке creators
+        sign_metadata(CpiContext::new(
+            ctx.accounts.token_metadata_program.to_account_info(),
+            SignMetadata {
+                creator: authority.to_account_info(),
+                metadata: ctx.accounts.metadata.to_account_info(),
+            },
+        ))?;
+        
+        // Увеличиваем счетчики треков
         track_nft.total_tracks = track_nft.total_tracks.checked_add(1).unwrap();
+        artist_profile.track_count = artist_profile.track_count.checked_add(1).unwrap();
         
         emit!(TrackCreatedEvent {
             track: track.key(),
-            creator: artist.key(),
+            mint: track.mint,
+            track_index: track.track_index,
+            creator: authority.key(),
             track_name: track.track_name.clone(),
             price,
             royalty_percentage,
+            max_editions,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
@@ -65,91 +145,679 @@ pub mod tracknft {
     }
 
     // Покупка трека
-    pub fn buy_track(ctx: Context<BuyTrack>, track_id: Pubkey) -> Result<()> {
+    pub fn buy_track<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyTrack<'info>>,
+        track_id: Pubkey,
+        tier: LicenseTier,
+    ) -> Result<()> {
         let track_nft = &mut ctx.accounts.track_nft;
         let track = &mut ctx.accounts.track;
         let buyer = &ctx.accounts.buyer;
         let artist = &ctx.accounts.artist;
-        let creator_royalties = &mut ctx.accounts.creator_royalties;
-        let platform_fees = &mut ctx.accounts.platform_fees;
+        let creator_royalties = &ctx.accounts.creator_royalties;
+        let platform_fees = &ctx.accounts.platform_fees;
         let authority = &ctx.accounts.authority;
         
         require!(track.is_listed, ErrorCode::TrackNotListed);
-        require!(buyer.key() != track.creator, ErrorCode::CreatorCannotBuy);
-        
-        let track_price = track.price;
-        let royalty_amount = track_price
-            .checked_mul(track.royalty_percentage as u64)
-            .unwrap()
-            .checked_div(100)
-            .unwrap();
-        
-        let platform_fee = track_price
-            .checked_mul(5) // 5% платформе
-            .unwrap()
-            .checked_div(100)
-            .unwrap();
-        
-        let artist_amount = track_price
-            .checked_sub(royalty_amount)
-            .unwrap()
-            .checked_sub(platform_fee)
-            .unwrap();
-        
-        // Переводим средства артисту
+        require!(buyer.owner != track.creator, ErrorCode::CreatorCannotBuy);
+        require!(splits_accepted(track), ErrorCode::SplitNotAccepted);
+        
+        // 0 - тираж не ограничен
+        require!(
+            track.max_editions == 0 || track.editions_sold < track.max_editions,
+            ErrorCode::SoldOut
+        );
+        
+        // Эксклюзив продается только до первой выданной лицензии
+        if tier == LicenseTier::Exclusive {
+            require!(track.editions_sold == 0, ErrorCode::LicensesAlreadyIssued);
+        }
+        
+        // Цена зависит от уровня лицензии
+        let track_price = license_price(track, tier)?;
+        let split = sale_split(track_price, track.royalty_percentage, track_nft.platform_fee_bps);
+        
+        // Доля артиста делится между соавторами, если они заданы
+        let artist_amount = split.seller_amount;
+        let direct_split = SaleSplit {
+            seller_amount: if track.collaborators.is_empty() { artist_amount } else { 0 },
+            ..split
+        };
+        
+        // Артист получает остаток, роялти - создателю, комиссия - платформе
+        pay_sale(
+            &ctx.accounts.token_program,
+            buyer,
+            authority.to_account_info(),
+            artist,
+            creator_royalties,
+            platform_fees,
+            &direct_split,
+            &[],
+        )?;
+        
+        if !track.collaborators.is_empty() {
+            pay_collaborators(
+                &ctx.accounts.token_program,
+                buyer,
+                authority.to_account_info(),
+                &track.collaborators,
+                ctx.remaining_accounts,
+                artist_amount,
+            )?;
+        }
+        
+        // Обновляем общие роялти
+        track.total_royalties_paid = track.total_royalties_paid.checked_add(split.royalty_amount).unwrap();
+        
+        // Печатаем покупателю нумерованную копию с master edition трека;
+        // metadata копии (включая верифицированного артиста) наследуется от master
+        let edition_number = track.editions_sold.checked_add(1).unwrap();
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        print_track_edition(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_metadata_program,
+            MintNewEditionFromMasterEditionViaToken {
+                new_metadata: ctx.accounts.edition_metadata.to_account_info(),
+                new_edition: ctx.accounts.print_edition.to_account_info(),
+                master_edition: ctx.accounts.master_edition.to_account_info(),
+                new_mint: ctx.accounts.edition_mint.to_account_info(),
+                edition_mark_pda: ctx.accounts.edition_marker.to_account_info(),
+                new_mint_authority: track_nft.to_account_info(),
+                payer: authority.to_account_info(),
+                token_account_owner: track_nft.to_account_info(),
+                token_account: ctx.accounts.master_token_account.to_account_info(),
+                new_metadata_update_authority: track_nft.to_account_info(),
+                metadata: ctx.accounts.master_metadata.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                metadata_mint: ctx.accounts.master_mint.to_account_info(),
+            },
+            ctx.accounts.edition_token_account.to_account_info(),
+            edition_number,
+            signer,
+        )?;
+        
+        let edition = &mut ctx.accounts.edition;
+        edition.track = track.key();
+        edition.mint = ctx.accounts.edition_mint.key();
+        edition.edition_number = edition_number;
+        edition.original_buyer = authority.key();
+        edition.purchased_at = Clock::get()?.unix_timestamp;
+        
+        // Лицензия - on-chain подтверждение покупки
+        let license = &mut ctx.accounts.license;
+        license.buyer = authority.key();
+        license.track = track.key();
+        license.edition_mint = edition.mint;
+        license.tier = tier;
+        license.price_paid = track_price;
+        license.purchased_at = edition.purchased_at;
+        license.terms_uri = track.terms_uri.clone();
+        
+        // Эксклюзивная лицензия снимает трек с продажи
+        if tier == LicenseTier::Exclusive {
+            track.exclusive_license = Some(license.key());
+            track.is_listed = false;
+        }
+        
+        // Обновляем счетчик продаж
+        track.editions_sold = edition_number;
+        track.sales_count = track.sales_count.checked_add(1).unwrap();
+        
+        emit!(TrackPurchasedEvent {
+            track: track.key(),
+            edition_mint: edition.mint,
+            edition_number,
+            license: license.key(),
+            tier,
+            buyer: buyer.key(),
+            artist: artist.key(),
+            price: track_price,
+            royalty_amount: split.royalty_amount,
+            platform_fee: split.platform_fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Выставление копии на вторичную продажу: NFT переходит в escrow листинга
+    pub fn list_edition(ctx: Context<ListEdition>, price: u64) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+        let seller = &ctx.accounts.seller;
+        
+        require!(price > 0, ErrorCode::InvalidPrice);
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.seller_edition_account.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                    authority: seller.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+        
+        listing.seller = seller.key();
+        listing.track = ctx.accounts.track.key();
+        listing.edition_mint = ctx.accounts.edition_mint.key();
+        listing.price = price;
+        listing.created_at = Clock::get()?.unix_timestamp;
+        
+        emit!(ListingCreatedEvent {
+            listing: listing.key(),
+            track: listing.track,
+            edition_mint: listing.edition_mint,
+            seller: listing.seller,
+            price,
+            timestamp: listing.created_at,
+        });
+        
+        Ok(())
+    }
+
+    // Снятие листинга: NFT возвращается продавцу
+    pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        let edition_mint = listing.edition_mint;
+        
+        let seeds = &[b"listing".as_ref(), edition_mint.as_ref(), &[ctx.bumps.listing]];
+        let signer = &[&seeds[..]];
+        
+        release_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow,
+            &ctx.accounts.seller_edition_account,
+            listing.to_account_info(),
+            ctx.accounts.seller.to_account_info(),
+            signer,
+        )?;
+        
+        emit!(ListingCancelledEvent {
+            listing: listing.key(),
+            edition_mint,
+            seller: listing.seller,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Покупка копии с вторичного рынка: роялти создателю взимается всегда
+    pub fn buy_listing(ctx: Context<BuyListing>) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let listing = &ctx.accounts.listing;
+        let buyer = &ctx.accounts.buyer;
+        let edition_mint = listing.edition_mint;
+        
+        let split = sale_split(listing.price, track.royalty_percentage, ctx.accounts.track_nft.platform_fee_bps);
+        
+        pay_sale(
+            &ctx.accounts.token_program,
+            &ctx.accounts.buyer_payment_account,
+            buyer.to_account_info(),
+            &ctx.accounts.seller_payment_account,
+            &ctx.accounts.creator_royalties,
+            &ctx.accounts.platform_fees,
+            &split,
+            &[],
+        )?;
+        
+        let seeds = &[b"listing".as_ref(), edition_mint.as_ref(), &[ctx.bumps.listing]];
+        let signer = &[&seeds[..]];
+        
+        release_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow,
+            &ctx.accounts.buyer_edition_account,
+            listing.to_account_info(),
+            ctx.accounts.seller.to_account_info(),
+            signer,
+        )?;
+        
+        track.total_royalties_paid = track.total_royalties_paid.checked_add(split.royalty_amount).unwrap();
+        track.sales_count = track.sales_count.checked_add(1).unwrap();
+        
+        emit!(ListingSoldEvent {
+            listing: listing.key(),
+            track: track.key(),
+            edition_mint,
+            seller: listing.seller,
+            buyer: buyer.key(),
+            price: listing.price,
+            royalty_amount: split.royalty_amount,
+            platform_fee: split.platform_fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Создание аукциона: NFT трека или копии переходит в escrow аукциона
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        kind: AuctionKind,
+        start_price: u64,
+        end_price: u64,
+        min_increment: u64,
+        duration: i64,
+        extension_window: i64,
+    ) -> Result<()> {
+        let auction = &mut ctx.accounts.auction;
+        let seller = &ctx.accounts.seller;
+        let track = &ctx.accounts.track;
+        let nft_mint = ctx.accounts.nft_mint.key();
+        
+        validate_auction_params(kind, start_price, end_price, min_increment, duration, extension_window)?;
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.seller_nft_account.to_account_info(),
+                    to: ctx.accounts.nft_escrow.to_account_info(),
+                    authority: seller.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+        
+        open_auction(
+            auction,
+            seller.key(),
+            track.key(),
+            nft_mint,
+            ctx.accounts.payment_mint.key(),
+            kind,
+            start_price,
+            end_price,
+            min_increment,
+            duration,
+            extension_window,
+        )
+    }
+
+    // Аукцион дропа: артист печатает новую копию с master edition трека
+    // сразу в escrow аукциона, победитель получает ее при расчете
+    pub fn create_drop_auction(
+        ctx: Context<CreateDropAuction>,
+        kind: AuctionKind,
+        start_price: u64,
+        end_price: u64,
+        min_increment: u64,
+        duration: i64,
+        extension_window: i64,
+    ) -> Result<()> {
+        let track_nft = &ctx.accounts.track_nft;
+        let track = &mut ctx.accounts.track;
+        let seller = &ctx.accounts.seller;
+        
+        require!(track.is_listed, ErrorCode::TrackNotListed);
+        // Выручка аукциона целиком уходит продавцу, доли соавторов не распределяются
+        require!(track.collaborators.is_empty(), ErrorCode::DropAuctionSplitUnsupported);
+        require!(
+            track.max_editions == 0 || track.editions_sold < track.max_editions,
+            ErrorCode::SoldOut
+        );
+        validate_auction_params(kind, start_price, end_price, min_increment, duration, extension_window)?;
+        
+        let edition_number = track.editions_sold.checked_add(1).unwrap();
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        print_track_edition(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_metadata_program,
+            MintNewEditionFromMasterEditionViaToken {
+                new_metadata: ctx.accounts.edition_metadata.to_account_info(),
+                new_edition: ctx.accounts.print_edition.to_account_info(),
+                master_edition: ctx.accounts.master_edition.to_account_info(),
+                new_mint: ctx.accounts.edition_mint.to_account_info(),
+                edition_mark_pda: ctx.accounts.edition_marker.to_account_info(),
+                new_mint_authority: track_nft.to_account_info(),
+                payer: seller.to_account_info(),
+                token_account_owner: track_nft.to_account_info(),
+                token_account: ctx.accounts.master_token_account.to_account_info(),
+                new_metadata_update_authority: track_nft.to_account_info(),
+                metadata: ctx.accounts.master_metadata.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                metadata_mint: ctx.accounts.master_mint.to_account_info(),
+            },
+            ctx.accounts.nft_escrow.to_account_info(),
+            edition_number,
+            signer,
+        )?;
+        
+        // Покупатель станет известен только при расчете аукциона
+        let edition = &mut ctx.accounts.edition;
+        edition.track = track.key();
+        edition.mint = ctx.accounts.edition_mint.key();
+        edition.edition_number = edition_number;
+        edition.original_buyer = seller.key();
+        edition.purchased_at = Clock::get()?.unix_timestamp;
+        
+        track.editions_sold = edition_number;
+        
+        open_auction(
+            &mut ctx.accounts.auction,
+            seller.key(),
+            track.key(),
+            edition.mint,
+            ctx.accounts.payment_mint.key(),
+            kind,
+            start_price,
+            end_price,
+            min_increment,
+            duration,
+            extension_window,
+        )
+    }
+
+    // Ставка в английском аукционе. amount - полная ставка участника,
+    // в escrow доплачивается разница с его прошлой ставкой.
+    pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        let auction = &mut ctx.accounts.auction;
+        let bid = &mut ctx.accounts.bid;
+        let bidder = &ctx.accounts.bidder;
+        
+        require!(auction.kind == AuctionKind::English, ErrorCode::WrongAuctionKind);
+        require!(bidder.key() != auction.seller, ErrorCode::Unauthorized);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(current_time < auction.end_time, ErrorCode::AuctionEnded);
+        
+        let min_bid = if auction.highest_bidder.is_some() {
+            auction.highest_bid.checked_add(auction.min_increment).unwrap()
+        } else {
+            auction.start_price
+        };
+        require!(amount >= min_bid, ErrorCode::BidTooLow);
+        
         anchor_spl::token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 anchor_spl::token::Transfer {
-                    from: buyer.to_account_info(),
-                    to: artist.to_account_info(),
-                    authority: authority.to_account_info(),
+                    from: ctx.accounts.bidder_payment_account.to_account_info(),
+                    to: ctx.accounts.bid_vault.to_account_info(),
+                    authority: bidder.to_account_info(),
                 },
             ),
-            artist_amount,
+            amount.checked_sub(bid.amount).unwrap(),
         )?;
         
-        // Выплачиваем роялти создателю (если это не артист)
-        if artist.key() != track.creator {
+        bid.auction = auction.key();
+        bid.bidder = bidder.key();
+        bid.amount = amount;
+        
+        auction.highest_bid = amount;
+        auction.highest_bidder = Some(bidder.key());
+        
+        // Защита от снайпинга: ставка в конце продлевает аукцион
+        if auction.end_time.checked_sub(current_time).unwrap() < auction.extension_window {
+            auction.end_time = current_time.checked_add(auction.extension_window).unwrap();
+        }
+        
+        emit!(BidPlacedEvent {
+            auction: auction.key(),
+            bidder: bidder.key(),
+            amount,
+            end_time: auction.end_time,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Возврат перебитой ставки из escrow
+    pub fn withdraw_bid(ctx: Context<WithdrawBid>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        let bid = &ctx.accounts.bid;
+        let nft_mint = auction.nft_mint;
+        
+        // Лидирующая ставка заблокирована до завершения аукциона
+        require!(
+            auction.settled || auction.highest_bidder != Some(bid.bidder),
+            ErrorCode::BidIsLeading
+        );
+        
+        let seeds = &[b"auction".as_ref(), nft_mint.as_ref(), &[ctx.bumps.auction]];
+        let signer = &[&seeds[..]];
+        
+        if bid.amount > 0 {
             anchor_spl::token::transfer(
-                CpiContext::new(
+                CpiContext::new_with_signer(
                     ctx.accounts.token_program.to_account_info(),
                     anchor_spl::token::Transfer {
-                        from: buyer.to_account_info(),
-                        to: creator_royalties.to_account_info(),
-                        authority: authority.to_account_info(),
+                        from: ctx.accounts.bid_vault.to_account_info(),
+                        to: ctx.accounts.bidder_payment_account.to_account_info(),
+                        authority: auction.to_account_info(),
                     },
+                    signer,
                 ),
-                royalty_amount,
+                bid.amount,
+            )?;
+        }
+        
+        emit!(BidWithdrawnEvent {
+            auction: auction.key(),
+            bidder: bid.bidder,
+            amount: bid.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Завершение английского аукциона (может вызвать любой после окончания).
+    // Без ставок NFT возвращается продавцу.
+    pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        let track = &mut ctx.accounts.track;
+        let nft_mint = auction.nft_mint;
+        
+        require!(auction.kind == AuctionKind::English, ErrorCode::WrongAuctionKind);
+        require!(!auction.settled, ErrorCode::AuctionSettled);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(current_time >= auction.end_time, ErrorCode::AuctionNotEnded);
+        
+        let seeds = &[b"auction".as_ref(), nft_mint.as_ref(), &[ctx.bumps.auction]];
+        let signer = &[&seeds[..]];
+        
+        let split = sale_split(auction.highest_bid, track.royalty_percentage, ctx.accounts.track_nft.platform_fee_bps);
+        
+        if let Some(winner) = auction.highest_bidder {
+            // Ставка победителя расходуется на оплату
+            let winner_bid = ctx.accounts.winner_bid.as_mut().ok_or(ErrorCode::BidNotFound)?;
+            require!(
+                winner_bid.auction == auction.key() && winner_bid.bidder == winner,
+                ErrorCode::BidNotFound
+            );
+            winner_bid.amount = 0;
+            
+            pay_sale(
+                &ctx.accounts.token_program,
+                &ctx.accounts.bid_vault,
+                auction.to_account_info(),
+                &ctx.accounts.seller_payment_account,
+                &ctx.accounts.creator_royalties,
+                &ctx.accounts.platform_fees,
+                &split,
+                signer,
             )?;
             
-            // Обновляем общие роялти
-            track.total_royalties_paid = track.total_royalties_paid.checked_add(royalty_amount).unwrap();
+            track.total_royalties_paid = track.total_royalties_paid.checked_add(split.royalty_amount).unwrap();
+            track.sales_count = track.sales_count.checked_add(1).unwrap();
         }
         
-        // Выплачиваем платформе
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::Transfer {
-                    from: buyer.to_account_info(),
-                    to: platform_fees.to_account_info(),
-                    authority: authority.to_account_info(),
-                },
-            ),
-            platform_fee,
+        release_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.nft_escrow,
+            &ctx.accounts.nft_destination,
+            auction.to_account_info(),
+            ctx.accounts.seller.to_account_info(),
+            signer,
         )?;
         
-        // Обновляем счетчик продаж
-        track.play_count = track.play_count.checked_add(1).unwrap();
+        let auction = &mut ctx.accounts.auction;
+        auction.settled = true;
         
-        emit!(TrackPurchasedEvent {
+        emit!(AuctionSettledEvent {
+            auction: auction.key(),
             track: track.key(),
-            buyer: buyer.key(),
-            artist: artist.key(),
-            price: track_price,
-            royalty_amount,
-            platform_fee,
+            winner: auction.highest_bidder,
+            price: auction.highest_bid,
+            royalty_amount: split.royalty_amount,
+            platform_fee: split.platform_fee,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Закрытие завершенного аукциона; оставшиеся ставки возвращаются участникам.
+    // Освобождает адрес аукциона для повторной продажи NFT.
+    pub fn close_auction<'info>(ctx: Context<'_, '_, '_, 'info, CloseAuction<'info>>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        let nft_mint = auction.nft_mint;
+        
+        require!(auction.settled, ErrorCode::AuctionNotEnded);
+        
+        let seeds = &[b"auction".as_ref(), nft_mint.as_ref(), &[ctx.bumps.auction]];
+        let signer = &[&seeds[..]];
+        
+        refund_bids(
+            &ctx.accounts.token_program,
+            &ctx.accounts.bid_vault,
+            auction,
+            ctx.remaining_accounts,
+            signer,
+        )?;
+        
+        ctx.accounts.bid_vault.reload()?;
+        require!(ctx.accounts.bid_vault.amount == 0, ErrorCode::BidsOutstanding);
+        
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::CloseAccount {
+                account: ctx.accounts.bid_vault.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: auction.to_account_info(),
+            },
+            signer,
+        ))?;
+        
+        Ok(())
+    }
+
+    // Покупка в голландском аукционе по текущей цене
+    pub fn buy_dutch(ctx: Context<BuyDutch>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        let track = &mut ctx.accounts.track;
+        let buyer = &ctx.accounts.buyer;
+        let nft_mint = auction.nft_mint;
+        
+        require!(auction.kind == AuctionKind::Dutch, ErrorCode::WrongAuctionKind);
+        
+        // После end_time продажа закрыта, продавец забирает NFT через cancel_auction
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(current_time < auction.end_time, ErrorCode::AuctionEnded);
+        let price = dutch_price(auction, current_time);
+        let split = sale_split(price, track.royalty_percentage, ctx.accounts.track_nft.platform_fee_bps);
+        
+        pay_sale(
+            &ctx.accounts.token_program,
+            &ctx.accounts.buyer_payment_account,
+            buyer.to_account_info(),
+            &ctx.accounts.seller_payment_account,
+            &ctx.accounts.creator_royalties,
+            &ctx.accounts.platform_fees,
+            &split,
+            &[],
+        )?;
+        
+        let seeds = &[b"auction".as_ref(), nft_mint.as_ref(), &[ctx.bumps.auction]];
+        let signer = &[&seeds[..]];
+        
+        release_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.nft_escrow,
+            &ctx.accounts.buyer_nft_account,
+            auction.to_account_info(),
+            ctx.accounts.seller.to_account_info(),
+            signer,
+        )?;
+        
+        // Хранилище ставок голландскому аукциону не нужно
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::CloseAccount {
+                account: ctx.accounts.bid_vault.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: auction.to_account_info(),
+            },
+            signer,
+        ))?;
+        
+        track.total_royalties_paid = track.total_royalties_paid.checked_add(split.royalty_amount).unwrap();
+        track.sales_count = track.sales_count.checked_add(1).unwrap();
+        
+        emit!(AuctionSettledEvent {
+            auction: auction.key(),
+            track: track.key(),
+            winner: Some(buyer.key()),
+            price,
+            royalty_amount: split.royalty_amount,
+            platform_fee: split.platform_fee,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Отмена аукциона продавцом до первой ставки
+    pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        let nft_mint = auction.nft_mint;
+        
+        require!(auction.highest_bidder.is_none(), ErrorCode::AuctionHasBids);
+        
+        let seeds = &[b"auction".as_ref(), nft_mint.as_ref(), &[ctx.bumps.auction]];
+        let signer = &[&seeds[..]];
+        
+        release_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.nft_escrow,
+            &ctx.accounts.seller_nft_account,
+            auction.to_account_info(),
+            ctx.accounts.seller.to_account_info(),
+            signer,
+        )?;
+        
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::CloseAccount {
+                account: ctx.accounts.bid_vault.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: auction.to_account_info(),
+            },
+            signer,
+        ))?;
+        
+        emit!(AuctionCancelledEvent {
+            auction: auction.key(),
+            seller: auction.seller,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
@@ -157,11 +825,41 @@ pub mod tracknft {
     }
 
     // Воспроизведение трека (для сбора аналитики)
-    pub fn play_track(ctx: Context<PlayTrack>, track_id: Pubkey) -> Result<()> {
+    // Засчитывается только воспроизведение, подписанное сервисом плеера
+    // (ed25519 инструкция перед текущей), не чаще min_play_interval на слушателя.
+    pub fn play_track(ctx: Context<PlayTrack>, track_id: Pubkey, played_at: i64) -> Result<()> {
+        let track_nft = &ctx.accounts.track_nft;
         let track = &mut ctx.accounts.track;
+        let play_record = &mut ctx.accounts.play_record;
         let listener = &ctx.accounts.listener;
         
         require!(track.is_listed, ErrorCode::TrackNotListed);
+        require!(track_nft.player_service != Pubkey::default(), ErrorCode::PlayerServiceNotConfigured);
+        
+        // Сообщение аттестации: track || listener || played_at
+        let mut message = Vec::with_capacity(32 + 32 + 8);
+        message.extend_from_slice(track.key().as_ref());
+        message.extend_from_slice(listener.key().as_ref());
+        message.extend_from_slice(&played_at.to_le_bytes());
+        verify_play_attestation(&ctx.accounts.instructions, &track_nft.player_service, &message)?;
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(played_at <= current_time, ErrorCode::InvalidPlayTimestamp);
+        
+        // Повтор старых аттестаций отсекается: прослушивание строго позже
+        // предыдущего засчитанного и не раньше чем через интервал
+        if play_record.play_count > 0 {
+            require!(
+                played_at > play_record.last_counted_at
+                    && played_at >= play_record.last_counted_at.checked_add(track_nft.min_play_interval).unwrap(),
+                ErrorCode::PlayTooSoon
+            );
+        }
+        
+        play_record.track = track.key();
+        play_record.listener = listener.key();
+        play_record.last_counted_at = played_at;
+        play_record.play_count = play_record.play_count.checked_add(1).unwrap();
         
         // Обновляем счетчик воспроизведений
         track.play_count = track.play_count.checked_add(1).unwrap();
@@ -169,75 +867,295 @@ pub mod tracknft {
         emit!(TrackPlayedEvent {
             track: track.key(),
             listener: listener.key(),
+            played_at,
+            play_count: track.play_count,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Комиссия платформы и хранилище, куда она поступает
+    pub fn update_platform_fee(ctx: Context<UpdatePlatformFee>, platform_fee_bps: u16) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(platform_fee_bps <= MAX_PLATFORM_FEE_BPS, ErrorCode::PlatformFeeTooHigh);
+        
+        track_nft.platform_fee_bps = platform_fee_bps;
+        
+        // Смена хранилища необязательна; валюта продаж не меняется
+        if let Some(fee_vault) = &ctx.accounts.fee_vault {
+            require!(fee_vault.mint == track_nft.payment_mint, ErrorCode::FeeVaultMintMismatch);
+            track_nft.fee_vault = fee_vault.key();
+        }
+        
+        emit!(PlatformFeeUpdatedEvent {
+            platform_fee_bps,
+            fee_vault: track_nft.fee_vault,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Настройка ключа сервиса плеера и интервала между засчитанными прослушиваниями
+    pub fn configure_player_service(
+        ctx: Context<ConfigurePlayerService>,
+        player_service: Pubkey,
+        min_play_interval: i64,
+    ) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(min_play_interval > 0, ErrorCode::InvalidPlayTimestamp);
+        
+        track_nft.player_service = player_service;
+        track_nft.min_play_interval = min_play_interval;
+        
+        emit!(PlayerServiceUpdatedEvent {
+            player_service,
+            min_play_interval,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    // Лайк трека
+    // Лайк трека: один на слушателя, повторный вызов ничего не меняет
     pub fn like_track(ctx: Context<LikeTrack>, track_id: Pubkey) -> Result<()> {
         let track = &mut ctx.accounts.track;
+        let like = &mut ctx.accounts.like;
         let listener = &ctx.accounts.listener;
         
         require!(track.is_listed, ErrorCode::TrackNotListed);
         
+        if like.liked_at != 0 {
+            return Ok(());
+        }
+        
+        like.track = track.key();
+        like.listener = listener.key();
+        like.liked_at = Clock::get()?.unix_timestamp;
+        
         // Обновляем счетчик лайков
         track.like_count = track.like_count.checked_add(1).unwrap();
         
         emit!(TrackLikedEvent {
             track: track.key(),
             listener: listener.key(),
-            timestamp: Clock::get()?.unix_timestamp,
+            like_count: track.like_count,
+            timestamp: like.liked_at,
         });
         
         Ok(())
     }
 
-    // Обновление цены трека
-    pub fn update_price(ctx: Context<UpdatePrice>, new_price: u64) -> Result<()> {
+    // Снятие лайка: аккаунт лайка закрывается с возвратом ренты
+    pub fn unlike_track(ctx: Context<UnlikeTrack>) -> Result<()> {
         let track = &mut ctx.accounts.track;
-        let authority = &ctx.accounts.authority;
-        
-        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        let listener = &ctx.accounts.listener;
         
-        track.price = new_price;
+        track.like_count = track.like_count.checked_sub(1).unwrap();
         
-        emit!(PriceUpdatedEvent {
+        emit!(TrackUnlikedEvent {
             track: track.key(),
-            new_price,
+            listener: listener.key(),
+            like_count: track.like_count,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    // Обновление роялти
-    pub fn update_royalty(ctx: Context<UpdateRoyalty>, new_royalty: u8) -> Result<()> {
+    // Цены лицензий sync/commercial/exclusive (0 - уровень не продается) и условия
+    pub fn update_license_terms(
+        ctx: Context<UpdateLicenseTerms>,
+        sync_price: u64,
+        commercial_price: u64,
+        exclusive_price: u64,
+        terms_uri: String,
+    ) -> Result<()> {
         let track = &mut ctx.accounts.track;
-        let track_nft = &mut ctx.accounts.track_nft;
         let authority = &ctx.accounts.authority;
         
         require!(authority.key() == track.creator, ErrorCode::Unauthorized);
-        require!(new_royalty <= 50, ErrorCode::RoyaltyTooHigh);
+        require!(terms_uri.len() <= MAX_URI_LENGTH, ErrorCode::UriTooLong);
         
-        track.royalty_percentage = new_royalty;
+        track.sync_price = sync_price;
+        track.commercial_price = commercial_price;
+        track.exclusive_price = exclusive_price;
+        track.terms_uri = terms_uri;
         
-        emit!(RoyaltyUpdatedEvent {
+        emit!(LicenseTermsUpdatedEvent {
             track: track.key(),
-            new_royalty,
+            personal_price: track.price,
+            sync_price,
+            commercial_price,
+            exclusive_price,
+            terms_uri: track.terms_uri.clone(),
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    // Списание/снятие с продажи
+    // Проверка лицензии третьими сторонами: лицензия следует за NFT копии,
+    // держателем считается текущий владелец токена
+    pub fn verify_license(ctx: Context<VerifyLicense>) -> Result<LicenseInfo> {
+        let license = &ctx.accounts.license;
+        let track = &ctx.accounts.track;
+        
+        Ok(LicenseInfo {
+            license: license.key(),
+            track: license.track,
+            edition_mint: license.edition_mint,
+            holder: ctx.accounts.holder_edition_account.owner,
+            original_buyer: license.buyer,
+            tier: license.tier,
+            price_paid: license.price_paid,
+            purchased_at: license.purchased_at,
+            terms_uri: license.terms_uri.clone(),
+            is_exclusive_holder: track.exclusive_license == Some(license.key()),
+        })
+    }
+
+    // Обновление цены трека
+    pub fn update_price(ctx: Context<UpdatePrice>, new_price: u64) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        
+        track.price = new_price;
+        
+        emit!(PriceUpdatedEvent {
+            track: track.key(),
+            new_price,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Обновление роялти
+    pub fn update_royalty(ctx: Context<UpdateRoyalty>, new_royalty: u8) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let track_nft = &mut ctx.accounts.track_nft;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        require!(new_royalty <= 50, ErrorCode::RoyaltyTooHigh);
+        
+        track.royalty_percentage = new_royalty;
+        
+        // Синхронизируем seller_fee_basis_points в metadata
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        update_metadata_accounts_v2(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                UpdateMetadataAccountsV2 {
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    update_authority: track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            None,
+            Some(track_metadata(track, track_nft.key(), true)),
+            None,
+            None,
+        )?;
+        
+        emit!(RoyaltyUpdatedEvent {
+            track: track.key(),
+            new_royalty,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Соавторы трека и их доли от выручки артиста (в сумме 10000 bps).
+    // Трек снимается с продажи, пока все соавторы не подтвердят доли.
+    pub fn set_collaborators(ctx: Context<SetCollaborators>, splits: Vec<CollaboratorSplit>) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        require!(splits.len() <= MAX_COLLABORATORS, ErrorCode::TooManyCollaborators);
+        
+        if !splits.is_empty() {
+            let total_bps = splits.iter().try_fold(0u16, |total, split| total.checked_add(split.bps));
+            require!(total_bps == Some(10_000), ErrorCode::InvalidSplitBps);
+            
+            for (i, split) in splits.iter().enumerate() {
+                require!(split.bps > 0, ErrorCode::InvalidSplitBps);
+                require!(
+                    splits[..i].iter().all(|other| other.wallet != split.wallet),
+                    ErrorCode::DuplicateCollaborator
+                );
+            }
+        }
+        
+        // Создатель подписывает транзакцию и принимает свою долю сразу
+        track.collaborators = splits
+            .iter()
+            .map(|split| Collaborator {
+                wallet: split.wallet,
+                bps: split.bps,
+                accepted: split.wallet == track.creator,
+            })
+            .collect();
+        
+        if !splits_accepted(track) {
+            track.is_listed = false;
+        }
+        
+        emit!(CollaboratorsUpdatedEvent {
+            track: track.key(),
+            collaborators: splits,
+            is_listed: track.is_listed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Подтверждение доли соавтором
+    pub fn accept_split(ctx: Context<AcceptSplit>) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let collaborator = &ctx.accounts.collaborator;
+        
+        let entry = track.collaborators
+            .iter_mut()
+            .find(|entry| entry.wallet == collaborator.key())
+            .ok_or(ErrorCode::NotCollaborator)?;
+        entry.accepted = true;
+        let bps = entry.bps;
+        
+        emit!(SplitAcceptedEvent {
+            track: track.key(),
+            collaborator: collaborator.key(),
+            bps,
+            all_accepted: splits_accepted(track),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Списание/снятие с продажи
     pub fn toggle_listing(ctx: Context<ToggleListing>) -> Result<()> {
         let track = &mut ctx.accounts.track;
         let authority = &ctx.accounts.authority;
         
         require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        require!(track.is_listed || splits_accepted(track), ErrorCode::SplitNotAccepted);
+        require!(track.is_listed || track.exclusive_license.is_none(), ErrorCode::ExclusivelyLicensed);
         
         track.is_listed = !track.is_listed;
         
@@ -268,12 +1186,520 @@ pub mod tracknft {
     }
 }
 
+// Ограничения Metaplex на поля metadata
+const MAX_NAME_LENGTH: usize = 32;
+const MAX_URI_LENGTH: usize = 200;
+const TRACK_SYMBOL: &str = "NDTRACK";
+const IPFS_URI_PREFIX: &str = "ipfs://";
+
+// Имя в metadata "<артист> - <трек>" в пределах лимита Metaplex
+fn metadata_name(artist_name: &str, track_name: &str) -> String {
+    let name = format!("{} - {}", artist_name, track_name);
+    let mut end = MAX_NAME_LENGTH.min(name.len());
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    
+    name[..end].to_string()
+}
+
+// Данные metadata трека. Первый создатель - PDA программы (доля 0),
+// второй - артист, получающий роялти целиком.
+fn track_metadata(track: &Track, track_nft: Pubkey, artist_verified: bool) -> DataV2 {
+    DataV2 {
+        name: metadata_name(&track.artist_name, &track.track_name),
+        symbol: TRACK_SYMBOL.to_string(),
+        uri: format!("{}{}", IPFS_URI_PREFIX, track.ipfs_hash),
+        seller_fee_basis_points: (track.royalty_percentage as u16) * 100,
+        creators: Some(vec![
+            Creator {
+                address: track_nft,
+                verified: true,
+                share: 0,
+            },
+            Creator {
+                address: track.creator,
+                verified: artist_verified,
+                share: 100,
+            },
+        ]),
+        collection: None,
+        uses: None,
+    }
+}
+
+// Выпуск NFT: один токен, metadata и master edition с тиражом max_supply (None - без ограничения).
+// Mint authority переходит к edition, update authority остается у программы.
+#[allow(clippy::too_many_arguments)]
+fn issue_nft<'info>(
+    token_program: &Program<'info, Token>,
+    token_metadata_program: &Program<'info, Metadata>,
+    system_program: &Program<'info, System>,
+    rent: &Sysvar<'info, Rent>,
+    mint: &Account<'info, Mint>,
+    token_account: &Account<'info, TokenAccount>,
+    metadata: &UncheckedAccount<'info>,
+    master_edition: &UncheckedAccount<'info>,
+    track_nft: &Account<'info, TrackNftState>,
+    payer: &Signer<'info>,
+    data: DataV2,
+    max_supply: Option<u64>,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    anchor_spl::token::mint_to(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            anchor_spl::token::MintTo {
+                mint: mint.to_account_info(),
+                to: token_account.to_account_info(),
+                authority: track_nft.to_account_info(),
+            },
+            signer,
+        ),
+        1,
+    )?;
+    
+    create_metadata_accounts_v3(
+        CpiContext::new_with_signer(
+            token_metadata_program.to_account_info(),
+            CreateMetadataAccountsV3 {
+                metadata: metadata.to_account_info(),
+                mint: mint.to_account_info(),
+                mint_authority: track_nft.to_account_info(),
+                payer: payer.to_account_info(),
+                update_authority: track_nft.to_account_info(),
+                system_program: system_program.to_account_info(),
+                rent: rent.to_account_info(),
+            },
+            signer,
+        ),
+        data,
+        true,
+        true,
+        None,
+    )?;
+    
+    create_master_edition_v3(
+        CpiContext::new_with_signer(
+            token_metadata_program.to_account_info(),
+            CreateMasterEditionV3 {
+                edition: master_edition.to_account_info(),
+                mint: mint.to_account_info(),
+                update_authority: track_nft.to_account_info(),
+                mint_authority: track_nft.to_account_info(),
+                payer: payer.to_account_info(),
+                metadata: metadata.to_account_info(),
+                token_program: token_program.to_account_info(),
+                system_program: system_program.to_account_info(),
+                rent: rent.to_account_info(),
+            },
+            signer,
+        ),
+        max_supply,
+    )
+}
+
+// Комиссия платформы в bps: по умолчанию 5%, не более 10%
+const DEFAULT_PLATFORM_FEE_BPS: u16 = 500;
+const MAX_PLATFORM_FEE_BPS: u16 = 1000;
+
+// Максимальное число соавторов трека
+const MAX_COLLABORATORS: usize = 8;
+
+// Распределение цены продажи
+#[derive(Clone, Copy)]
+pub struct SaleSplit {
+    pub royalty_amount: u64,
+    pub platform_fee: u64,
+    pub seller_amount: u64,
+}
+
+fn sale_split(price: u64, royalty_percentage: u8, platform_fee_bps: u16) -> SaleSplit {
+    let royalty_amount = price
+        .checked_mul(royalty_percentage as u64)
+        .unwrap()
+        .checked_div(100)
+        .unwrap();
+    
+    let platform_fee = price
+        .checked_mul(platform_fee_bps as u64)
+        .unwrap()
+        .checked_div(10_000)
+        .unwrap();
+    
+    let seller_amount = price
+        .checked_sub(royalty_amount)
+        .unwrap()
+        .checked_sub(platform_fee)
+        .unwrap();
+    
+    SaleSplit {
+        royalty_amount,
+        platform_fee,
+        seller_amount,
+    }
+}
+
+// Выплаты по продаже: продавцу, создателю (роялти) и платформе.
+// signer пустой, если средства списываются со счета подписанта транзакции.
+#[allow(clippy::too_many_arguments)]
+fn pay_sale<'info>(
+    token_program: &Program<'info, Token>,
+    from: &Account<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    seller: &Account<'info, TokenAccount>,
+    creator_royalties: &Account<'info, TokenAccount>,
+    platform_fees: &Account<'info, TokenAccount>,
+    split: &SaleSplit,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let payouts = [
+        (seller, split.seller_amount),
+        (creator_royalties, split.royalty_amount),
+        (platform_fees, split.platform_fee),
+    ];
+    
+    for (to, amount) in payouts {
+        if amount == 0 {
+            continue;
+        }
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: from.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: authority.clone(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+    }
+    
+    Ok(())
+}
+
+// Проверка ed25519 инструкции, идущей непосредственно перед текущей:
+// одна подпись, ключ и сообщение лежат в данных самой инструкции
+fn verify_play_attestation(instructions: &UncheckedAccount, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current_index = load_current_index_checked(instructions)?;
+    require!(current_index > 0, ErrorCode::InvalidPlayAttestation);
+    
+    let ed25519_ix = load_instruction_at_checked((current_index - 1) as usize, instructions)?;
+    require!(ed25519_ix.program_id == ed25519_program::ID, ErrorCode::InvalidPlayAttestation);
+    
+    // Заголовок: число подписей, выравнивание и 7 смещений u16
+    let data = &ed25519_ix.data;
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::InvalidPlayAttestation);
+    
+    let read_u16 = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+    let signature_ix_index = read_u16(4);
+    let public_key_offset = read_u16(6) as usize;
+    let public_key_ix_index = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    let message_ix_index = read_u16(14);
+    
+    require!(
+        signature_ix_index == u16::MAX && public_key_ix_index == u16::MAX && message_ix_index == u16::MAX,
+        ErrorCode::InvalidPlayAttestation
+    );
+    
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(ErrorCode::InvalidPlayAttestation)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(ErrorCode::InvalidPlayAttestation)?;
+    
+    require!(public_key == signer.as_ref(), ErrorCode::InvalidPlayAttestation);
+    require!(signed_message == message, ErrorCode::InvalidPlayAttestation);
+    
+    Ok(())
+}
+
+// Цена уровня лицензии; personal продается по Track.price
+fn license_price(track: &Track, tier: LicenseTier) -> Result<u64> {
+    let price = match tier {
+        LicenseTier::Personal => return Ok(track.price),
+        LicenseTier::Sync => track.sync_price,
+        LicenseTier::Commercial => track.commercial_price,
+        LicenseTier::Exclusive => track.exclusive_price,
+    };
+    require!(price > 0, ErrorCode::TierNotOffered);
+    
+    Ok(price)
+}
+
+// Все соавторы подтвердили свои доли (или соавторов нет)
+fn splits_accepted(track: &Track) -> bool {
+    track.collaborators.iter().all(|collaborator| collaborator.accepted)
+}
+
+// Выплата долей соавторам. Счета получателей передаются в remaining_accounts
+// в порядке track.collaborators; последний получает остаток от округления.
+fn pay_collaborators<'info>(
+    token_program: &Program<'info, Token>,
+    from: &Account<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    collaborators: &[Collaborator],
+    payout_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    require!(payout_accounts.len() == collaborators.len(), ErrorCode::InvalidCollaboratorAccount);
+    
+    let mut remaining = amount;
+    for (i, (collaborator, payout_account)) in collaborators.iter().zip(payout_accounts).enumerate() {
+        require!(payout_account.owner == &anchor_spl::token::ID, ErrorCode::InvalidCollaboratorAccount);
+        let token_account = TokenAccount::try_deserialize(&mut &payout_account.try_borrow_data()?[..])?;
+        require!(token_account.owner == collaborator.wallet, ErrorCode::InvalidCollaboratorAccount);
+        
+        let share = if i + 1 == collaborators.len() {
+            remaining
+        } else {
+            amount
+                .checked_mul(collaborator.bps as u64)
+                .unwrap()
+                .checked_div(10_000)
+                .unwrap()
+        };
+        remaining = remaining.checked_sub(share).unwrap();
+        
+        if share > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new(
+                    token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: from.to_account_info(),
+                        to: payout_account.clone(),
+                        authority: authority.clone(),
+                    },
+                ),
+                share,
+            )?;
+        }
+    }
+    
+    Ok(())
+}
+
+// Возврат невыведенных ставок при закрытии аукциона. remaining_accounts передаются
+// тройками: аккаунт ставки, счет оплаты участника, кошелек участника (получает ренту ставки).
+fn refund_bids<'info>(
+    token_program: &Program<'info, Token>,
+    bid_vault: &Account<'info, TokenAccount>,
+    auction: &Account<'info, Auction>,
+    refund_accounts: &[AccountInfo<'info>],
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    require!(refund_accounts.len() % 3 == 0, ErrorCode::InvalidRefundAccount);
+    
+    for accounts in refund_accounts.chunks(3) {
+        let (bid_info, payment_info, bidder_info) = (&accounts[0], &accounts[1], &accounts[2]);
+        
+        require!(bid_info.owner == &crate::ID, ErrorCode::InvalidRefundAccount);
+        let bid = Bid::try_deserialize(&mut &bid_info.try_borrow_data()?[..])?;
+        require!(bid.auction == auction.key(), ErrorCode::InvalidRefundAccount);
+        require!(bidder_info.key() == bid.bidder, ErrorCode::InvalidRefundAccount);
+        
+        require!(payment_info.owner == &anchor_spl::token::ID, ErrorCode::InvalidRefundAccount);
+        let payment_account = TokenAccount::try_deserialize(&mut &payment_info.try_borrow_data()?[..])?;
+        require!(
+            payment_account.owner == bid.bidder && payment_account.mint == auction.payment_mint,
+            ErrorCode::InvalidRefundAccount
+        );
+        
+        if bid.amount > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: bid_vault.to_account_info(),
+                        to: payment_info.clone(),
+                        authority: auction.to_account_info(),
+                    },
+                    signer,
+                ),
+                bid.amount,
+            )?;
+        }
+        
+        // Закрываем аккаунт ставки, рента возвращается участнику
+        let rent = bid_info.lamports();
+        **bidder_info.try_borrow_mut_lamports()? = bidder_info.lamports().checked_add(rent).unwrap();
+        **bid_info.try_borrow_mut_lamports()? = 0;
+        bid_info.assign(&anchor_lang::system_program::ID);
+        bid_info.realloc(0, false)?;
+        
+        emit!(BidWithdrawnEvent {
+            auction: auction.key(),
+            bidder: bid.bidder,
+            amount: bid.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+    
+    Ok(())
+}
+
+// Проверка параметров нового аукциона
+fn validate_auction_params(
+    kind: AuctionKind,
+    start_price: u64,
+    end_price: u64,
+    min_increment: u64,
+    duration: i64,
+    extension_window: i64,
+) -> Result<()> {
+    require!(start_price > 0, ErrorCode::InvalidPrice);
+    require!(duration > 0 && extension_window >= 0, ErrorCode::InvalidAuctionParams);
+    match kind {
+        AuctionKind::English => require!(min_increment > 0, ErrorCode::InvalidAuctionParams),
+        AuctionKind::Dutch => require!(start_price > end_price, ErrorCode::InvalidAuctionParams),
+    }
+    
+    Ok(())
+}
+
+// Заполнение аукциона, NFT которого уже лежит в escrow
+#[allow(clippy::too_many_arguments)]
+fn open_auction(
+    auction: &mut Account<Auction>,
+    seller: Pubkey,
+    track: Pubkey,
+    nft_mint: Pubkey,
+    payment_mint: Pubkey,
+    kind: AuctionKind,
+    start_price: u64,
+    end_price: u64,
+    min_increment: u64,
+    duration: i64,
+    extension_window: i64,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    auction.seller = seller;
+    auction.track = track;
+    auction.nft_mint = nft_mint;
+    auction.payment_mint = payment_mint;
+    auction.kind = kind;
+    auction.start_price = start_price;
+    auction.end_price = end_price;
+    auction.min_increment = min_increment;
+    auction.start_time = current_time;
+    auction.end_time = current_time.checked_add(duration).unwrap();
+    auction.extension_window = extension_window;
+    auction.highest_bid = 0;
+    auction.highest_bidder = None;
+    auction.settled = false;
+    
+    emit!(AuctionCreatedEvent {
+        auction: auction.key(),
+        track,
+        nft_mint,
+        seller,
+        kind,
+        start_price,
+        end_price,
+        end_time: auction.end_time,
+        timestamp: current_time,
+    });
+    
+    Ok(())
+}
+
+// Печать нумерованной копии с master edition трека: токен копии выпускается в destination,
+// mint authority новой копии и владелец master token account - PDA программы
+fn print_track_edition<'info>(
+    token_program: &Program<'info, Token>,
+    token_metadata_program: &Program<'info, Metadata>,
+    accounts: MintNewEditionFromMasterEditionViaToken<'info>,
+    destination: AccountInfo<'info>,
+    edition_number: u64,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    anchor_spl::token::mint_to(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            anchor_spl::token::MintTo {
+                mint: accounts.new_mint.clone(),
+                to: destination,
+                authority: accounts.new_mint_authority.clone(),
+            },
+            signer,
+        ),
+        1,
+    )?;
+    
+    mint_new_edition_from_master_edition_via_token(
+        CpiContext::new_with_signer(token_metadata_program.to_account_info(), accounts, signer),
+        edition_number,
+    )
+}
+
+// Цена голландского аукциона линейно снижается до end_price к end_time
+fn dutch_price(auction: &Auction, now: i64) -> u64 {
+    if now >= auction.end_time {
+        return auction.end_price;
+    }
+    
+    let elapsed = now.checked_sub(auction.start_time).unwrap().max(0) as u128;
+    let duration = auction.end_time.checked_sub(auction.start_time).unwrap() as u128;
+    let price_drop = ((auction.start_price - auction.end_price) as u128)
+        .checked_mul(elapsed)
+        .unwrap()
+        .checked_div(duration)
+        .unwrap() as u64;
+    
+    auction.start_price.checked_sub(price_drop).unwrap()
+}
+
+// Выдача NFT из escrow и закрытие escrow с возвратом ренты
+fn release_escrow<'info>(
+    token_program: &Program<'info, Token>,
+    escrow: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    escrow_authority: AccountInfo<'info>,
+    rent_receiver: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: escrow.to_account_info(),
+                to: to.to_account_info(),
+                authority: escrow_authority.clone(),
+            },
+            signer,
+        ),
+        1,
+    )?;
+    
+    anchor_spl::token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        anchor_spl::token::CloseAccount {
+            account: escrow.to_account_info(),
+            destination: rent_receiver,
+            authority: escrow_authority,
+        },
+        signer,
+    ))
+}
+
 // Accounts
 #[account]
 pub struct TrackNftState {
     pub authority: Pubkey,
     pub royalty_percentage: u8, // Default royalty percentage
     pub total_tracks: u64,
+    pub player_service: Pubkey, // Signs play attestations
+    pub min_play_interval: i64, // Seconds between counted plays per listener
+    pub platform_fee_bps: u16,
+    pub fee_vault: Pubkey, // Only token account accepted as platform_fees
+    pub payment_mint: Pubkey, // Mint of every sale payment (fee_vault mint)
 }
 
 #[account]
@@ -281,14 +1707,150 @@ pub struct Track {
     pub track_name: String,
     pub artist_name: String,
     pub ipfs_hash: String,
-    pub price: u64,
+    pub price: u64, // Personal license price
+    pub sync_price: u64, // 0 = tier not offered
+    pub commercial_price: u64,
+    pub exclusive_price: u64,
+    pub terms_uri: String,
+    pub exclusive_license: Option<Pubkey>,
     pub royalty_percentage: u8,
+    pub max_editions: u64, // 0 = unlimited
+    pub editions_sold: u64,
     pub is_listed: bool,
     pub play_count: u64,
+    pub sales_count: u64,
     pub like_count: u64,
     pub total_royalties_paid: u64,
     pub creator: Pubkey,
+    pub track_index: u64,
+    pub mint: Pubkey,
     pub mint_time: i64,
+    pub collaborators: Vec<Collaborator>, // Empty = artist takes the whole artist amount
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Collaborator {
+    pub wallet: Pubkey,
+    pub bps: u16,
+    pub accepted: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct CollaboratorSplit {
+    pub wallet: Pubkey,
+    pub bps: u16,
+}
+
+// Адрес трека: [b"track", artist, track_index]
+#[account]
+pub struct ArtistProfile {
+    pub artist: Pubkey,
+    pub track_count: u64,
+    pub created_at: i64,
+}
+
+// Нумерованная копия трека, выпущенная при покупке
+#[account]
+pub struct Edition {
+    pub track: Pubkey,
+    pub mint: Pubkey,
+    pub edition_number: u64,
+    pub original_buyer: Pubkey,
+    pub purchased_at: i64,
+}
+
+// Листинг копии на вторичном рынке; NFT хранится в escrow листинга
+#[account]
+pub struct Listing {
+    pub seller: Pubkey,
+    pub track: Pubkey,
+    pub edition_mint: Pubkey,
+    pub price: u64,
+    pub created_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionKind {
+    English,
+    Dutch,
+}
+
+// Аукцион NFT трека или копии; NFT в escrow, ставки в bid_vault
+#[account]
+pub struct Auction {
+    pub seller: Pubkey,
+    pub track: Pubkey,
+    pub nft_mint: Pubkey,
+    pub payment_mint: Pubkey,
+    pub kind: AuctionKind,
+    pub start_price: u64,
+    pub end_price: u64, // Dutch: floor price
+    pub min_increment: u64, // English only
+    pub start_time: i64,
+    pub end_time: i64,
+    pub extension_window: i64, // English: anti-sniping, seconds
+    pub highest_bid: u64,
+    pub highest_bidder: Option<Pubkey>,
+    pub settled: bool,
+}
+
+// Ставка участника английского аукциона, хранится в bid_vault до возврата
+#[account]
+pub struct Bid {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+}
+
+// Засчитанные прослушивания слушателя по треку
+#[account]
+pub struct PlayRecord {
+    pub track: Pubkey,
+    pub listener: Pubkey,
+    pub last_counted_at: i64,
+    pub play_count: u64,
+}
+
+// Лайк слушателя: [b"like", track, listener]
+#[account]
+pub struct Like {
+    pub track: Pubkey,
+    pub listener: Pubkey,
+    pub liked_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LicenseTier {
+    Personal,
+    Sync,
+    Commercial,
+    Exclusive,
+}
+
+// Лицензия, выданная при покупке: [b"license", edition_mint]
+#[account]
+pub struct License {
+    pub buyer: Pubkey,
+    pub track: Pubkey,
+    pub edition_mint: Pubkey,
+    pub tier: LicenseTier,
+    pub price_paid: u64,
+    pub purchased_at: i64,
+    pub terms_uri: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LicenseInfo {
+    pub license: Pubkey,
+    pub track: Pubkey,
+    pub edition_mint: Pubkey,
+    pub holder: Pubkey,
+    pub original_buyer: Pubkey,
+    pub tier: LicenseTier,
+    pub price_paid: u64,
+    pub purchased_at: i64,
+    pub terms_uri: String,
+    pub is_exclusive_holder: bool,
 }
 
 #[account]
@@ -302,16 +1864,30 @@ pub struct RoyaltyAccount {
 #[event]
 pub struct TrackCreatedEvent {
     pub track: Pubkey,
+    pub mint: Pubkey,
+    pub track_index: u64,
     pub creator: Pubkey,
     pub track_name: String,
     pub price: u64,
     pub royalty_percentage: u8,
+    pub max_editions: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ArtistRegisteredEvent {
+    pub artist: Pubkey,
+    pub profile: Pubkey,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct TrackPurchasedEvent {
     pub track: Pubkey,
+    pub edition_mint: Pubkey,
+    pub edition_number: u64,
+    pub license: Pubkey,
+    pub tier: LicenseTier,
     pub buyer: Pubkey,
     pub artist: Pubkey,
     pub price: u64,
@@ -321,107 +1897,927 @@ pub struct TrackPurchasedEvent {
 }
 
 #[event]
-pub struct TrackPlayedEvent {
+pub struct LicenseTermsUpdatedEvent {
     pub track: Pubkey,
-    pub listener: Pubkey,
+    pub personal_price: u64,
+    pub sync_price: u64,
+    pub commercial_price: u64,
+    pub exclusive_price: u64,
+    pub terms_uri: String,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct TrackLikedEvent {
+pub struct CollaboratorsUpdatedEvent {
     pub track: Pubkey,
-    pub listener: Pubkey,
+    pub collaborators: Vec<CollaboratorSplit>,
+    pub is_listed: bool,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct PriceUpdatedEvent {
+pub struct SplitAcceptedEvent {
     pub track: Pubkey,
-    pub new_price: u64,
+    pub collaborator: Pubkey,
+    pub bps: u16,
+    pub all_accepted: bool,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct RoyaltyUpdatedEvent {
+pub struct ListingCreatedEvent {
+    pub listing: Pubkey,
     pub track: Pubkey,
-    pub new_royalty: u8,
+    pub edition_mint: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct ListingToggledEvent {
+pub struct ListingCancelledEvent {
+    pub listing: Pubkey,
+    pub edition_mint: Pubkey,
+    pub seller: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingSoldEvent {
+    pub listing: Pubkey,
     pub track: Pubkey,
-    pub is_listed: bool,
+    pub edition_mint: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub royalty_amount: u64,
+    pub platform_fee: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct RoyaltiesPaidEvent {
-    pub total_amount: u64,
+pub struct AuctionCreatedEvent {
+    pub auction: Pubkey,
+    pub track: Pubkey,
+    pub nft_mint: Pubkey,
+    pub seller: Pubkey,
+    pub kind: AuctionKind,
+    pub start_price: u64,
+    pub end_price: u64,
+    pub end_time: i64,
     pub timestamp: i64,
 }
 
-// Contexts
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(init, payer = authority, seeds = [b"tracknft"], bump)]
-    pub track_nft: Account<'info, TrackNftState>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct BidPlacedEvent {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub end_time: i64,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct CreateTrack<'info> {
-    #[account(mut, seeds = [b"tracknft"], bump)]
-    pub track_nft: Account<'info, TrackNftState>,
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 32 + 32 + 200 + 200 + 8 + 1 + 8 + 8 + 8 + 32 + 8,
-        seeds = [b"track", artist.key().as_ref(), &Clock::get()?.unix_timestamp.to_le_bytes()],
-        bump
-    )]
-    pub track: Account<'info, Track>,
-    #[account(mut)]
-    pub artist: Account<'info, TokenAccount>,
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct BidWithdrawnEvent {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct BuyTrack<'info> {
-    #[account(mut, seeds = [b"tracknft"], bump)]
-    pub track_nft: Account<'info, TrackNftState>,
+#[event]
+pub struct AuctionSettledEvent {
+    pub auction: Pubkey,
+    pub track: Pubkey,
+    pub winner: Option<Pubkey>,
+    pub price: u64,
+    pub royalty_amount: u64,
+    pub platform_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionCancelledEvent {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackPlayedEvent {
+    pub track: Pubkey,
+    pub listener: Pubkey,
+    pub played_at: i64,
+    pub play_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlayerServiceUpdatedEvent {
+    pub player_service: Pubkey,
+    pub min_play_interval: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackLikedEvent {
+    pub track: Pubkey,
+    pub listener: Pubkey,
+    pub like_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackUnlikedEvent {
+    pub track: Pubkey,
+    pub listener: Pubkey,
+    pub like_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceUpdatedEvent {
+    pub track: Pubkey,
+    pub new_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoyaltyUpdatedEvent {
+    pub track: Pubkey,
+    pub new_royalty: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingToggledEvent {
+    pub track: Pubkey,
+    pub is_listed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlatformFeeUpdatedEvent {
+    pub platform_fee_bps: u16,
+    pub fee_vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoyaltiesPaidEvent {
+    pub total_amount: u64,
+    pub timestamp: i64,
+}
+
+// Contexts
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 8
+            + 32 + 8
+            + 2 + 32 + 32,
+        seeds = [b"tracknft"],
+        bump
+    )]
+    pub track_nft: Account<'info, TrackNftState>,
+    pub fee_vault: Account<'info, TokenAccount>,
     #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterArtist<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 8,
+        seeds = [b"artist", authority.key().as_ref()],
+        bump
+    )]
+    pub artist_profile: Account<'info, ArtistProfile>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTrack<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 200 + 200 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + 32 + 8
+            + 8 + 8 + 8 + 4 + MAX_URI_LENGTH + 33
+            + 4 + MAX_COLLABORATORS * (32 + 2 + 1),
+        seeds = [b"track", authority.key().as_ref(), &artist_profile.track_count.to_le_bytes()],
+        bump
+    )]
     pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        seeds = [b"artist", authority.key().as_ref()],
+        bump,
+        constraint = artist_profile.artist == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub artist_profile: Account<'info, ArtistProfile>,
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = track_nft,
+        mint::freeze_authority = track_nft
+    )]
+    pub mint: Account<'info, Mint>,
+    // Master NFT хранится у программы для печати копий
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = track_nft
+    )]
+    pub master_token_account: Account<'info, TokenAccount>,
+    /// CHECK: создается программой Metaplex, адрес проверен seeds
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: создается программой Metaplex, адрес проверен seeds
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref(), b"edition"],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub master_edition: UncheckedAccount<'info>,
     #[account(mut)]
-    pub artist: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct BuyTrack<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
     #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        constraint = artist.owner == track.creator @ ErrorCode::InvalidArtistAccount,
+        constraint = artist.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub artist: Account<'info, TokenAccount>,
+    #[account(mut, constraint = buyer.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint)]
     pub buyer: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_royalties.owner == track.creator @ ErrorCode::InvalidRoyaltyAccount,
+        constraint = creator_royalties.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub creator_royalties: Account<'info, TokenAccount>,
+    #[account(mut, address = track_nft.fee_vault @ ErrorCode::InvalidFeeVault)]
+    pub platform_fees: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = track_nft,
+        mint::freeze_authority = track_nft
+    )]
+    pub edition_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = edition_mint,
+        associated_token::authority = authority
+    )]
+    pub edition_token_account: Account<'info, TokenAccount>,
+    /// CHECK: создается программой Metaplex, адрес проверен seeds
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), edition_mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub edition_metadata: UncheckedAccount<'info>,
+    /// CHECK: создается программой Metaplex, адрес проверен seeds
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), edition_mint.key().as_ref(), b"edition"],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub print_edition: UncheckedAccount<'info>,
+    #[account(address = track.mint)]
+    pub master_mint: Account<'info, Mint>,
+    #[account(
+        associated_token::mint = master_mint,
+        associated_token::authority = track_nft
+    )]
+    pub master_token_account: Account<'info, TokenAccount>,
+    /// CHECK: metadata master NFT, адрес проверен seeds
+    #[account(
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), master_mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub master_metadata: UncheckedAccount<'info>,
+    /// CHECK: master edition трека, адрес проверен seeds
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), master_mint.key().as_ref(), b"edition"],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+    /// CHECK: маркер номера копии, адрес проверяет программа Metaplex
+    #[account(mut)]
+    pub edition_marker: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 32 + 8,
+        seeds = [b"edition", edition_mint.key().as_ref()],
+        bump
+    )]
+    pub edition: Account<'info, Edition>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 1 + 8 + 8 + 4 + MAX_URI_LENGTH,
+        seeds = [b"license", edition_mint.key().as_ref()],
+        bump
+    )]
+    pub license: Account<'info, License>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ListEdition<'info> {
+    pub track: Account<'info, Track>,
+    #[account(
+        seeds = [b"edition", edition_mint.key().as_ref()],
+        bump,
+        constraint = edition.track == track.key() @ ErrorCode::EditionTrackMismatch
+    )]
+    pub edition: Account<'info, Edition>,
+    pub edition_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = seller_edition_account.mint == edition_mint.key() @ ErrorCode::NotEditionHolder,
+        constraint = seller_edition_account.owner == seller.key() @ ErrorCode::NotEditionHolder,
+        constraint = seller_edition_account.amount == 1 @ ErrorCode::NotEditionHolder
+    )]
+    pub seller_edition_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + 32 + 32 + 32 + 8 + 8,
+        seeds = [b"listing", edition_mint.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = edition_mint,
+        associated_token::authority = listing
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"listing", listing.edition_mint.as_ref()],
+        bump,
+        has_one = seller @ ErrorCode::Unauthorized
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        associated_token::mint = listing.edition_mint,
+        associated_token::authority = listing
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = seller_edition_account.mint == listing.edition_mint,
+        constraint = seller_edition_account.owner == seller.key()
+    )]
+    pub seller_edition_account: Account<'info, TokenAccount>,
     #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyListing<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, address = listing.track)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"listing", listing.edition_mint.as_ref()],
+        bump,
+        has_one = seller
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        associated_token::mint = listing.edition_mint,
+        associated_token::authority = listing
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_edition_account.mint == listing.edition_mint,
+        constraint = buyer_edition_account.owner == buyer.key()
+    )]
+    pub buyer_edition_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_payment_account.owner == buyer.key(),
+        constraint = buyer_payment_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub buyer_payment_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = seller_payment_account.owner == listing.seller,
+        constraint = seller_payment_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub seller_payment_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_royalties.owner == track.creator @ ErrorCode::InvalidRoyaltyAccount,
+        constraint = creator_royalties.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub creator_royalties: Account<'info, TokenAccount>,
+    #[account(mut, address = track_nft.fee_vault @ ErrorCode::InvalidFeeVault)]
+    pub platform_fees: Account<'info, TokenAccount>,
+    /// CHECK: получает ренту листинга и escrow, совпадает с listing.seller
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    pub buyer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAuction<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    pub track: Account<'info, Track>,
+    // Перепродажа копий: новые копии артист выставляет через create_drop_auction
+    #[account(
+        seeds = [b"edition", nft_mint.key().as_ref()],
+        bump,
+        constraint = edition.track == track.key() @ ErrorCode::EditionTrackMismatch
+    )]
+    pub edition: Account<'info, Edition>,
+    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = seller_nft_account.mint == nft_mint.key() @ ErrorCode::NotEditionHolder,
+        constraint = seller_nft_account.owner == seller.key() @ ErrorCode::NotEditionHolder,
+        constraint = seller_nft_account.amount == 1 @ ErrorCode::NotEditionHolder
+    )]
+    pub seller_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + 32 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 33 + 1,
+        seeds = [b"auction", nft_mint.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = nft_mint,
+        associated_token::authority = auction
+    )]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    #[account(address = track_nft.payment_mint @ ErrorCode::InvalidPaymentMint)]
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = seller,
+        token::mint = payment_mint,
+        token::authority = auction,
+        seeds = [b"auction_vault", auction.key().as_ref()],
+        bump
+    )]
+    pub bid_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDropAuction<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, constraint = track.creator == seller.key() @ ErrorCode::Unauthorized)]
+    pub track: Account<'info, Track>,
+    #[account(
+        init,
+        payer = seller,
+        mint::decimals = 0,
+        mint::authority = track_nft,
+        mint::freeze_authority = track_nft
+    )]
+    pub edition_mint: Account<'info, Mint>,
+    /// CHECK: создается программой Metaplex, адрес проверен seeds
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), edition_mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub edition_metadata: UncheckedAccount<'info>,
+    /// CHECK: создается программой Metaplex, адрес проверен seeds
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), edition_mint.key().as_ref(), b"edition"],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub print_edition: UncheckedAccount<'info>,
+    #[account(address = track.mint)]
+    pub master_mint: Account<'info, Mint>,
+    #[account(
+        associated_token::mint = master_mint,
+        associated_token::authority = track_nft
+    )]
+    pub master_token_account: Account<'info, TokenAccount>,
+    /// CHECK: metadata master NFT, адрес проверен seeds
+    #[account(
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), master_mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub master_metadata: UncheckedAccount<'info>,
+    /// CHECK: master edition трека, адрес проверен seeds
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), master_mint.key().as_ref(), b"edition"],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+    /// CHECK: маркер номера копии, адрес проверяет программа Metaplex
+    #[account(mut)]
+    pub edition_marker: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + 32 + 32 + 8 + 32 + 8,
+        seeds = [b"edition", edition_mint.key().as_ref()],
+        bump
+    )]
+    pub edition: Account<'info, Edition>,
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + 32 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 33 + 1,
+        seeds = [b"auction", edition_mint.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = edition_mint,
+        associated_token::authority = auction
+    )]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    #[account(address = track_nft.payment_mint @ ErrorCode::InvalidPaymentMint)]
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = seller,
+        token::mint = payment_mint,
+        token::authority = auction,
+        seeds = [b"auction_vault", auction.key().as_ref()],
+        bump
+    )]
+    pub bid_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut, seeds = [b"auction", auction.nft_mint.as_ref()], bump)]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        space = 8 + 32 + 32 + 8,
+        seeds = [b"bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(mut, seeds = [b"auction_vault", auction.key().as_ref()], bump)]
+    pub bid_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = bidder_payment_account.owner == bidder.key())]
+    pub bidder_payment_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBid<'info> {
+    #[account(seeds = [b"auction", auction.nft_mint.as_ref()], bump)]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        close = bidder,
+        seeds = [b"bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(mut, seeds = [b"auction_vault", auction.key().as_ref()], bump)]
+    pub bid_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = bidder_payment_account.owner == bidder.key())]
+    pub bidder_payment_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, seeds = [b"auction", auction.nft_mint.as_ref()], bump, has_one = seller)]
+    pub auction: Account<'info, Auction>,
+    #[account(mut, address = auction.track)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        associated_token::mint = auction.nft_mint,
+        associated_token::authority = auction
+    )]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    // Победитель, а без ставок - продавец
+    #[account(
+        mut,
+        constraint = nft_destination.mint == auction.nft_mint,
+        constraint = nft_destination.owner == auction.highest_bidder.unwrap_or(auction.seller)
+    )]
+    pub nft_destination: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub winner_bid: Option<Account<'info, Bid>>,
+    #[account(mut, seeds = [b"auction_vault", auction.key().as_ref()], bump)]
+    pub bid_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = seller_payment_account.owner == auction.seller,
+        constraint = seller_payment_account.mint == auction.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub seller_payment_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_royalties.owner == track.creator @ ErrorCode::InvalidRoyaltyAccount,
+        constraint = creator_royalties.mint == auction.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
     pub creator_royalties: Account<'info, TokenAccount>,
+    #[account(mut, address = track_nft.fee_vault @ ErrorCode::InvalidFeeVault)]
+    pub platform_fees: Account<'info, TokenAccount>,
+    /// CHECK: получает ренту escrow, совпадает с auction.seller
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAuction<'info> {
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"auction", auction.nft_mint.as_ref()],
+        bump,
+        has_one = seller
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(mut, seeds = [b"auction_vault", auction.key().as_ref()], bump)]
+    pub bid_vault: Account<'info, TokenAccount>,
+    /// CHECK: получает ренту аукциона, совпадает с auction.seller
     #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyDutch<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"auction", auction.nft_mint.as_ref()],
+        bump,
+        has_one = seller
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(mut, address = auction.track)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        associated_token::mint = auction.nft_mint,
+        associated_token::authority = auction
+    )]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_nft_account.mint == auction.nft_mint,
+        constraint = buyer_nft_account.owner == buyer.key()
+    )]
+    pub buyer_nft_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"auction_vault", auction.key().as_ref()], bump)]
+    pub bid_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_payment_account.owner == buyer.key(),
+        constraint = buyer_payment_account.mint == auction.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub buyer_payment_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = seller_payment_account.owner == auction.seller,
+        constraint = seller_payment_account.mint == auction.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub seller_payment_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_royalties.owner == track.creator @ ErrorCode::InvalidRoyaltyAccount,
+        constraint = creator_royalties.mint == auction.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub creator_royalties: Account<'info, TokenAccount>,
+    #[account(mut, address = track_nft.fee_vault @ ErrorCode::InvalidFeeVault)]
     pub platform_fees: Account<'info, TokenAccount>,
-    pub authority: Signer<'info>,
+    /// CHECK: получает ренту аукциона и escrow, совпадает с auction.seller
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    pub buyer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuction<'info> {
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"auction", auction.nft_mint.as_ref()],
+        bump,
+        has_one = seller @ ErrorCode::Unauthorized
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        associated_token::mint = auction.nft_mint,
+        associated_token::authority = auction
+    )]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"auction_vault", auction.key().as_ref()], bump)]
+    pub bid_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = seller_nft_account.mint == auction.nft_mint,
+        constraint = seller_nft_account.owner == seller.key()
+    )]
+    pub seller_nft_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
 pub struct PlayTrack<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
     #[account(mut)]
     pub track: Account<'info, Track>,
+    #[account(
+        init_if_needed,
+        payer = listener,
+        space = 8 + 32 + 32 + 8 + 8,
+        seeds = [b"play", track.key().as_ref(), listener.key().as_ref()],
+        bump
+    )]
+    pub play_record: Account<'info, PlayRecord>,
+    /// CHECK: sysvar инструкций, адрес проверен
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    #[account(mut)]
     pub listener: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePlatformFee<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    pub fee_vault: Option<Account<'info, TokenAccount>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigurePlayerService<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct LikeTrack<'info> {
     #[account(mut)]
     pub track: Account<'info, Track>,
+    #[account(
+        init_if_needed,
+        payer = listener,
+        space = 8 + 32 + 32 + 8,
+        seeds = [b"like", track.key().as_ref(), listener.key().as_ref()],
+        bump
+    )]
+    pub like: Account<'info, Like>,
+    #[account(mut)]
+    pub listener: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnlikeTrack<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        close = listener,
+        seeds = [b"like", track.key().as_ref(), listener.key().as_ref()],
+        bump
+    )]
+    pub like: Account<'info, Like>,
+    #[account(mut)]
     pub listener: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateLicenseTerms<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyLicense<'info> {
+    #[account(seeds = [b"license", license.edition_mint.as_ref()], bump)]
+    pub license: Account<'info, License>,
+    #[account(address = license.track)]
+    pub track: Account<'info, Track>,
+    #[account(
+        constraint = holder_edition_account.mint == license.edition_mint @ ErrorCode::NotEditionHolder,
+        constraint = holder_edition_account.amount == 1 @ ErrorCode::NotEditionHolder
+    )]
+    pub holder_edition_account: Account<'info, TokenAccount>,
+}
+
 #[derive(Accounts)]
 pub struct UpdatePrice<'info> {
     #[account(mut)]
@@ -435,7 +2831,30 @@ pub struct UpdateRoyalty<'info> {
     pub track: Account<'info, Track>,
     #[account(mut, seeds = [b"tracknft"], bump)]
     pub track_nft: Account<'info, TrackNftState>,
+    /// CHECK: metadata mint трека, адрес проверен seeds
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), track.mint.as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
     pub authority: Signer<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollaborators<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptSplit<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    pub collaborator: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -466,4 +2885,78 @@ pub enum ErrorCode {
     CreatorCannotBuy,
     #[msg("Royalty percentage too high (max 50%)")]
     RoyaltyTooHigh,
+    #[msg("Track name is too long")]
+    NameTooLong,
+    #[msg("Metadata URI is too long")]
+    UriTooLong,
+    #[msg("All editions of this track are sold")]
+    SoldOut,
+    #[msg("Price must be greater than zero")]
+    InvalidPrice,
+    #[msg("Edition does not belong to this track")]
+    EditionTrackMismatch,
+    #[msg("Signer does not hold this edition")]
+    NotEditionHolder,
+    #[msg("Royalties must be paid to the track creator")]
+    InvalidRoyaltyAccount,
+    #[msg("Invalid auction parameters")]
+    InvalidAuctionParams,
+    #[msg("Operation is not supported for this auction type")]
+    WrongAuctionKind,
+    #[msg("Auction has ended")]
+    AuctionEnded,
+    #[msg("Auction has not ended yet")]
+    AuctionNotEnded,
+    #[msg("Auction is already settled")]
+    AuctionSettled,
+    #[msg("Auction already has bids")]
+    AuctionHasBids,
+    #[msg("Bid is below the minimum")]
+    BidTooLow,
+    #[msg("Leading bid cannot be withdrawn before settlement")]
+    BidIsLeading,
+    #[msg("Winning bid account not provided")]
+    BidNotFound,
+    #[msg("Not every outstanding bid was refunded")]
+    BidsOutstanding,
+    #[msg("Too many collaborators")]
+    TooManyCollaborators,
+    #[msg("Collaborator shares must be positive and sum to 10000 bps")]
+    InvalidSplitBps,
+    #[msg("Collaborator listed twice")]
+    DuplicateCollaborator,
+    #[msg("Signer is not a collaborator on this track")]
+    NotCollaborator,
+    #[msg("Not all collaborators have accepted their split")]
+    SplitNotAccepted,
+    #[msg("Collaborator payout accounts do not match the track splits")]
+    InvalidCollaboratorAccount,
+    #[msg("Player service key is not configured")]
+    PlayerServiceNotConfigured,
+    #[msg("Missing or invalid play attestation")]
+    InvalidPlayAttestation,
+    #[msg("Invalid play timestamp")]
+    InvalidPlayTimestamp,
+    #[msg("Play counted too recently for this listener")]
+    PlayTooSoon,
+    #[msg("License tier is not offered for this track")]
+    TierNotOffered,
+    #[msg("Track is exclusively licensed")]
+    ExclusivelyLicensed,
+    #[msg("Platform fee too high (max 10%)")]
+    PlatformFeeTooHigh,
+    #[msg("Platform fees must go to the configured fee vault")]
+    InvalidFeeVault,
+    #[msg("Fee vault must hold the payment mint")]
+    FeeVaultMintMismatch,
+    #[msg("Payment account does not hold the payment mint")]
+    InvalidPaymentMint,
+    #[msg("Invalid bid refund account")]
+    InvalidRefundAccount,
+    #[msg("Sale proceeds must go to the track creator")]
+    InvalidArtistAccount,
+    #[msg("Exclusive license is unavailable once other licenses are issued")]
+    LicensesAlreadyIssued,
+    #[msg("Drop auctions are unavailable for tracks with collaborators")]
+    DropAuctionSplitUnsupported,
 }
\ No newline at end of file